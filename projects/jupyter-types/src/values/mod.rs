@@ -6,6 +6,10 @@ pub trait Executed: Send {
     fn mime_type(&self) -> String;
     /// Convert the result to json.
     fn as_json(&self, context: &JupyterContext) -> Value;
+    /// The binary buffers sent along with the json, e.g. arrow record batches, without base64 encoding.
+    fn as_buffers(&self) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
 }
 
 /// The running context of the Jupyter notebook
@@ -1,18 +1,35 @@
 use crate::{
-    connection::Connection,
+    connection::{Connection, SignatureScheme},
     errors::JupyterResult,
     jupyter_message::{JupyterMessage, JupyterMessageType},
-    ExecutionRequest, JupyterConnection, JupyterKernelProtocol, JupyterKernelSockets,
+    ExecutionError, ExecutionReply, ExecutionRequest, JupyterConnection, JupyterError, JupyterKernelProtocol,
+    JupyterKernelSockets, LanguageInfo,
 };
 
 use crate::{
     commands::start::KernelControl,
-    jupyter_message::{CommonInfoRequest, KernelInfoReply},
+    executor::history::JupyterHistory,
+    jupyter_message::{
+        CommonInfoRequest, CompletionRequest, ExecutionInput, HistoryRequest, InspectionRequest, InterruptReply,
+        IsCompleteRequest, KernelInfoReply, ShutdownRequest,
+    },
 };
 use serde_json::Value;
-use std::{sync::Arc, time::SystemTime};
-use tokio::{sync::Mutex, task::JoinHandle};
-use zeromq::{PubSocket, RepSocket, RouterSocket, Socket, SocketRecv, SocketSend, ZmqMessage};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        Mutex, Notify,
+    },
+    task::JoinHandle,
+};
+use zeromq::{Endpoint, PubSocket, RepSocket, ReqSocket, RouterSocket, Socket, SocketRecv, SocketSend, ZmqMessage};
 
 // Note, to avoid potential deadlocks, each thread should lock at most one mutex at a time.
 #[derive(Clone)]
@@ -24,11 +41,62 @@ pub(crate) struct SealedServer {
     control: Arc<Mutex<Connection<RouterSocket>>>,
     shell_socket: Arc<Mutex<Connection<RouterSocket>>>,
     latest_execution_request: Arc<Mutex<Option<JupyterMessage>>>,
-    // execution_request_receiver: Arc<Mutex<UnboundedReceiver<ExecutionResult>>>,
+    history: Arc<Mutex<JupyterHistory>>,
+    /// The arrival order of execute requests, which decides the requests to abort
+    execution_order: Arc<ExecutionOrder>,
+    execution_request_sender: UnboundedSender<(u64, JupyterMessage)>,
+    execution_request_receiver: Arc<Mutex<UnboundedReceiver<(u64, JupyterMessage)>>>,
+    /// The replies of the execution queue, sent by the shell loop which owns the shell socket while waiting
+    shell_reply_sender: UnboundedSender<(u64, JupyterMessage)>,
+    shell_reply_receiver: Arc<Mutex<UnboundedReceiver<(u64, JupyterMessage)>>>,
+    /// Cached to answer `kernel_info_request` without waiting for the running execution
+    language_info: Arc<LanguageInfo>,
     shutdown_sender: Arc<Mutex<Option<crossbeam_channel::Sender<()>>>>,
     tokio_handle: tokio::runtime::Handle,
 }
 
+/// Execute requests are numbered when the shell receives them, from `1`.
+#[derive(Debug, Default)]
+struct ExecutionOrder {
+    /// The number of the latest received request
+    received: AtomicU64,
+    /// Requests numbered up to this are aborted instead of executed
+    abort_until: AtomicU64,
+    /// The number of the latest request whose reply has been sent on the shell
+    replied: AtomicU64,
+    notify: Notify,
+}
+
+impl ExecutionOrder {
+    fn next(&self) -> u64 {
+        self.received.fetch_add(1, Ordering::SeqCst) + 1
+    }
+    /// Abort all requests received so far, as ipykernel does when an execution fails with `stop_on_error`
+    fn abort_received(&self) -> u64 {
+        let received = self.received.load(Ordering::SeqCst);
+        self.abort_until.fetch_max(received, Ordering::SeqCst);
+        received
+    }
+    fn is_aborted(&self, number: u64) -> bool {
+        number <= self.abort_until.load(Ordering::SeqCst)
+    }
+    fn set_replied(&self, number: u64) {
+        self.replied.fetch_max(number, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+    /// Wait until the requests numbered up to `number` have been replied
+    async fn replied(&self, number: u64) {
+        loop {
+            // register before checking, so that a reply between them is not missed
+            let notified = self.notify.notified();
+            if self.replied.load(Ordering::SeqCst) >= number {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
 pub struct ExecuteProvider<T> {
     pub(crate) context: Arc<Mutex<T>>,
     pub(crate) sockets: JupyterKernelSockets,
@@ -76,13 +144,15 @@ impl SealedServer {
             .build()
             .unwrap();
         let handle = runtime.handle().clone();
-        runtime.block_on(async {
+        config.remove_ipc_sockets();
+        let result = runtime.block_on(async {
             let shutdown_receiver = Self::start(config, handle, server).await?;
             shutdown_receiver.wait_for_shutdown().await;
             let result: JupyterResult<()> = Ok(());
             result
-        })?;
-        Ok(())
+        });
+        config.remove_ipc_sockets();
+        result
     }
 
     async fn start<T>(
@@ -93,19 +163,37 @@ impl SealedServer {
     where
         T: JupyterKernelProtocol + 'static,
     {
-        let heartbeat = bind_socket::<RepSocket>(config, config.hb_port).await?;
-        let shell_socket = bind_socket::<RouterSocket>(config, config.shell_port).await?;
-        let control_socket = bind_socket::<RouterSocket>(config, config.control_port).await?;
-        let stdin_socket = bind_socket::<RouterSocket>(config, config.stdin_port).await?;
-        let io_pub_socket = bind_socket::<PubSocket>(config, config.iopub_port).await?;
+        let (heartbeat, hb_port) = bind_socket::<RepSocket>(config, config.hb_port).await?;
+        let (shell_socket, shell_port) = bind_socket::<RouterSocket>(config, config.shell_port).await?;
+        let (control_socket, control_port) = bind_socket::<RouterSocket>(config, config.control_port).await?;
+        let (stdin_socket, stdin_port) = bind_socket::<RouterSocket>(config, config.stdin_port).await?;
+        let (io_pub_socket, iopub_port) = bind_socket::<PubSocket>(config, config.iopub_port).await?;
+        if let Some(port) = config.registration_port {
+            // the launcher compares the scheme with its own, so it is sent in the normalized form
+            let signature_scheme = config.signature_scheme.parse::<SignatureScheme>()?.as_str().to_string();
+            let bound =
+                KernelControl { hb_port, shell_port, control_port, stdin_port, iopub_port, signature_scheme, ..config.clone() };
+            register(&bound, port).await?;
+        }
         let io_pub = Arc::new(Mutex::new(io_pub_socket));
         let (shutdown_sender, shutdown_receiver) = crossbeam_channel::unbounded();
+        let (execution_sender, execution_receiver) = unbounded_channel();
+        let (shell_reply_sender, shell_reply_receiver) = unbounded_channel();
         let latest_execution_request = Arc::new(Mutex::new(None));
+        let history = Arc::new(Mutex::new(JupyterHistory::new(server.history_file())));
+        let stdin = Arc::new(Mutex::new(stdin_socket));
         let sockets = JupyterKernelSockets {
-            execute_count: Arc::new(Mutex::new(1)),
+            stdin_channel: Some(stdin.clone()),
+            execute_count: Default::default(),
             io_channel: Some(io_pub.clone()),
             debugging: Arc::new(Mutex::new(false)),
+            silent: Default::default(),
+            interrupt: Default::default(),
+            comms: Default::default(),
+            widgets: Default::default(),
         };
+        sockets.register_widget_target();
+        sockets.register_lint_target();
         let setup = JupyterConnection { boot_path: Default::default(), sockets: sockets.clone() };
         server.connected(setup);
         // server.bind_execution_socket(execution_result_sender).await;
@@ -113,8 +201,14 @@ impl SealedServer {
             iopub: io_pub,
             heartbeat: Arc::new(Mutex::new(heartbeat)),
             latest_execution_request,
-            // execution_request_receiver: Arc::new(Mutex::new(execution_receiver)),
-            stdin: Arc::new(Mutex::new(stdin_socket)),
+            history,
+            execution_order: Default::default(),
+            execution_request_sender: execution_sender,
+            execution_request_receiver: Arc::new(Mutex::new(execution_receiver)),
+            shell_reply_sender,
+            shell_reply_receiver: Arc::new(Mutex::new(shell_reply_receiver)),
+            language_info: Arc::new(server.language_info()),
+            stdin,
             control: Arc::new(Mutex::new(control_socket)),
             shutdown_sender: Arc::new(Mutex::new(Some(shutdown_sender))),
             tokio_handle,
@@ -123,14 +217,14 @@ impl SealedServer {
         let context = ExecuteProvider::new(server, sockets);
         here.clone().spawn_heart_beat();
         here.clone().spawn_shell_execution(context.clone());
-        // server.clone().spawn_execution_queue(context.clone());
+        here.clone().spawn_execution_queue(context.clone());
         here.clone().spawn_control(context.clone());
-        here.clone().spawn_std_in(context.clone());
+        #[cfg(unix)]
+        here.clone().spawn_signal_interrupt(context.clone());
         Ok(ShutdownReceiver { recv: shutdown_receiver })
     }
 
     async fn signal_shutdown(&self) {
-        // let msg = ShutdownReply::new();
         self.shutdown_sender.lock().await.take();
     }
 
@@ -160,8 +254,11 @@ impl SealedServer {
         tokio::spawn(async move {
             tracing::info!("Shell Executor Spawned");
             loop {
-                if let Err(e) = self.clone().handle_shell(executor.clone()).await {
-                    tracing::error!("Error sending shell execution: {:?}", e);
+                match self.clone().handle_shell(executor.clone()).await {
+                    // already counted and logged by the connection
+                    Err(e) if e.is_malformed_message() => {}
+                    Err(e) => tracing::error!("Error sending shell execution: {:?}", e),
+                    Ok(_) => {}
                 }
             }
         })
@@ -173,45 +270,62 @@ impl SealedServer {
         // Processing of every message should be enclosed between "busy" and "idle"
         // see https://jupyter-client.readthedocs.io/en/latest/messaging.html#messages-on-the-shell-router-dealer-channel
         // Jupiter Lab doesn't use the kernel until it received "idle" for kernel_info_request
-        let request = JupyterMessage::read(&mut &mut self.shell_socket.lock().await).await?;
+        let request = {
+            let replies = &mut self.shell_reply_receiver.lock().await;
+            let shell = &mut self.shell_socket.lock().await;
+            tokio::select! {
+                request = JupyterMessage::read(shell) => request?,
+                Some((number, reply)) = replies.recv() => {
+                    let sent = reply.send_by(shell).await;
+                    // counted even if the frontend is gone, the restart should not wait forever
+                    self.execution_order.set_replied(number);
+                    return sent;
+                }
+            }
+        };
+        if let JupyterMessageType::ExecuteRequest = request.kind() {
+            // run in order by the execution queue, which also reports busy and idle
+            self.execution_request_sender.send((self.execution_order.next(), request))?;
+            return Ok(());
+        }
         request.send_state(self.iopub.clone(), true).await?;
         match request.kind() {
             JupyterMessageType::KernelInfoRequest => {
-                let info = executor.context.lock().await.language_info();
-                let cont = KernelInfoReply::build(info);
+                let cont = KernelInfoReply::build(&self.language_info, &request.header);
                 request.as_reply().with_content(cont)?.send_by(&mut &mut self.shell_socket.lock().await).await?
             }
-            JupyterMessageType::ExecuteRequest => {
-                let time = SystemTime::now();
-                // *self.latest_execution_request.lock().await = Some(request);
-                let mut task = request.recast::<ExecutionRequest>()?;
-                task.header = request.clone();
-                // reply busy event
-                let mut runner = executor.context.lock().await;
-                let count = executor.sockets.get_counter();
-                let reply = runner.running(task.clone()).await.with_count(count);
-                // Check elapsed time
-                match time.elapsed() {
-                    Ok(o) => {
-                        let escape = runner.running_time(o.as_secs_f64());
-                        if !escape.is_empty() {
-                            let time = task.as_result("text/html".to_string(), Value::String(escape));
-                            request
-                                .as_reply()
-                                .with_message_type(JupyterMessageType::ExecuteResult)
-                                .with_content(time)?
-                                .send_by(&mut &mut self.iopub.lock().await)
-                                .await?;
-                        }
-                    }
-                    Err(_) => {}
-                }
-                // reply finish event
-                request.as_reply().with_content(reply)?.send_by(&mut &mut self.shell_socket.lock().await).await?;
+            JupyterMessageType::CompleteRequest => {
+                let task = request.recast::<CompletionRequest>()?;
+                let reply = executor.context.lock().await.complete(&task.code, task.cursor_pos);
+                let shell = &mut self.shell_socket.lock().await;
+                request.as_reply().with_content(reply)?.send_by(shell).await?;
+            }
+            JupyterMessageType::InspectRequest => {
+                let task = request.recast::<InspectionRequest>()?;
+                let found = executor.context.lock().await.inspect_code(&task.code, task.cursor_pos, task.detail_level);
+                let shell = &mut self.shell_socket.lock().await;
+                request.as_reply().with_content(task.as_reply(found.as_deref()))?.send_by(shell).await?;
+            }
+            JupyterMessageType::HistoryRequest => {
+                let task = request.recast::<HistoryRequest>()?;
+                let history = self.history.lock().await.query(&task.access);
+                let shell = &mut self.shell_socket.lock().await;
+                request.as_reply().with_content(task.as_reply(history))?.send_by(shell).await?;
+            }
+            JupyterMessageType::IsCompleteRequest => {
+                let task = request.recast::<IsCompleteRequest>()?;
+                let reply = executor.context.lock().await.is_complete(&task.code);
+                let shell = &mut self.shell_socket.lock().await;
+                request.as_reply().with_content(reply)?.send_by(shell).await?;
             }
             JupyterMessageType::CommonInfoRequest => {
                 let task = request.recast::<CommonInfoRequest>()?;
-                request.as_reply().with_content(task.as_reply())?.send_by(&mut &mut self.shell_socket.lock().await).await?;
+                let comms = executor.sockets.comm_info();
+                let shell = &mut self.shell_socket.lock().await;
+                request.as_reply().with_content(task.as_reply(comms))?.send_by(shell).await?;
+            }
+            JupyterMessageType::CommOpen | JupyterMessageType::CommMessage | JupyterMessageType::CommClose => {
+                executor.sockets.handle_comm(&request).await?;
             }
             JupyterMessageType::Custom(v) => {
                 tracing::error!("Got unknown shell message: {:?}", v);
@@ -223,30 +337,134 @@ impl SealedServer {
         request.send_state(self.iopub, false).await?;
         Ok(())
     }
-    #[allow(dead_code)]
     fn spawn_execution_queue<T>(self, executor: ExecuteProvider<T>) -> JoinHandle<()>
     where
         T: JupyterKernelProtocol + Send + 'static,
     {
-        let mut running_count = 0;
         tokio::spawn(async move {
             tracing::trace!("Queue Executor Spawned");
             loop {
-                if let Err(e) = self.clone().handle_execution_queue(executor.clone(), running_count).await {
-                    eprintln!("Error sending execution queue: {:?}", e);
+                if let Err(e) = self.clone().handle_execution_queue(executor.clone()).await {
+                    tracing::error!("Error sending execution queue: {:?}", e);
                 }
-                running_count += 1;
             }
         })
     }
-    #[allow(dead_code)]
-    async fn handle_execution_queue<T>(self, _executor: ExecuteProvider<T>, _count: i32) -> JupyterResult<()>
+    async fn handle_execution_queue<T>(self, executor: ExecuteProvider<T>) -> JupyterResult<()>
+    where
+        T: JupyterKernelProtocol + Send + 'static,
+    {
+        let (number, request) = match self.execution_request_receiver.lock().await.recv().await {
+            Some(o) => o,
+            None => return Err(JupyterError::channel_block("execution queue")),
+        };
+        request.send_state(self.iopub.clone(), true).await?;
+        let result = self.execute(&executor, number, &request).await;
+        if let Err(e) = &result {
+            // every request gets a reply, the restart waits for it
+            let reply = ExecutionReply::from(ExecutionError::from(e.clone())).with_count(executor.sockets.get_counter());
+            self.shell_reply_sender.send((number, request.as_reply().with_content(reply)?))?;
+        }
+        request.send_state(self.iopub.clone(), false).await?;
+        result
+    }
+    async fn execute<T>(&self, executor: &ExecuteProvider<T>, number: u64, request: &JupyterMessage) -> JupyterResult<()>
+    where
+        T: JupyterKernelProtocol + Send + 'static,
+    {
+        let time = SystemTime::now();
+        // *self.latest_execution_request.lock().await = Some(request);
+        let mut task = request.recast::<ExecutionRequest>()?;
+        task.header = request.clone();
+        task.metadata = request.metadata().as_object().cloned().unwrap_or_default();
+        // interrupts before this execution do not count
+        executor.sockets.interrupt.reset();
+        task.interrupt = executor.sockets.interrupt.clone();
+        // the request arrived before the failure, the user has not seen the error yet
+        if self.execution_order.is_aborted(number) {
+            let aborted = ExecutionReply::aborted().with_count(executor.sockets.get_counter());
+            self.shell_reply_sender.send((number, request.as_reply().with_content(aborted)?))?;
+            return Ok(());
+        }
+        let mut runner = executor.context.lock().await;
+        // silent executions do not count, and share the count of the previous cell
+        let count = match task.silent {
+            true => executor.sockets.get_counter(),
+            false => executor.sockets.next_counter(),
+        };
+        if task.store_history && !task.silent {
+            self.history.lock().await.push(count, task.code.clone());
+        }
+        if !task.silent {
+            let input = ExecutionInput { code: task.code.clone(), execution_count: count };
+            let iopub = &mut self.iopub.lock().await;
+            request.create_message(JupyterMessageType::ExecuteInput).with_content(input)?.send_by(iopub).await?;
+        }
+        executor.sockets.set_silent(task.silent);
+        let mut reply = runner.running(task.clone()).await.with_count(count);
+        if reply.is_success() {
+            if let Value::Object(expressions) = &task.user_expressions {
+                for (name, expression) in expressions {
+                    let result = match expression.as_str() {
+                        Some(code) => runner.evaluate_expression(code),
+                        None => Err(ExecutionError::new("TypeError", "user expression must be a string")),
+                    };
+                    reply = reply.with_expression(name, result);
+                }
+            }
+        }
+        // Check elapsed time
+        match time.elapsed() {
+            Ok(o) if !task.silent => {
+                let escape = runner.running_time(o.as_secs_f64());
+                if !escape.is_empty() {
+                    let time = task.as_result("text/html".to_string(), Value::String(escape));
+                    request
+                        .as_reply()
+                        .with_message_type(JupyterMessageType::ExecuteResult)
+                        .with_content(time)?
+                        .send_by(&mut &mut self.iopub.lock().await)
+                        .await?;
+                }
+            }
+            _ => {}
+        }
+        if let Some(error) = reply.error() {
+            executor.sockets.send_error(error, request).await?;
+        }
+        executor.sockets.set_silent(false);
+        if !reply.is_success() && task.stop_on_error && !task.silent {
+            self.execution_order.abort_received();
+        }
+        // reply finish event
+        self.shell_reply_sender.send((number, request.as_reply().with_content(reply)?))?;
+        Ok(())
+    }
+
+    /// Interrupt mode `signal` sends `SIGINT` instead of `interrupt_request`
+    #[cfg(unix)]
+    fn spawn_signal_interrupt<T>(self, executor: ExecuteProvider<T>) -> JoinHandle<()>
     where
         T: JupyterKernelProtocol + Send + 'static,
     {
-        // let io = self.iopub.try_lock()?;
-        // let exec = self.execution_request_receiver.try_lock()?;
-        todo!()
+        use tokio::signal::unix::{signal, SignalKind};
+        tokio::spawn(async move {
+            let mut interrupts = match signal(SignalKind::interrupt()) {
+                Ok(o) => o,
+                Err(e) => {
+                    tracing::warn!("Failed to listen SIGINT: {:?}", e);
+                    return;
+                }
+            };
+            while interrupts.recv().await.is_some() {
+                self.interrupt(&executor.sockets);
+            }
+        })
+    }
+    /// Only stops the running execution, queued requests are aborted if it fails with `stop_on_error`
+    fn interrupt(&self, sockets: &JupyterKernelSockets) {
+        // never wait for the kernel lock, which is held by the running execution
+        sockets.interrupt.interrupt();
     }
 
     fn spawn_control<T>(self, executor: ExecuteProvider<T>) -> JoinHandle<()>
@@ -256,8 +474,10 @@ impl SealedServer {
         tokio::spawn(async move {
             tracing::info!("Control Executor Spawned");
             loop {
-                if let Err(e) = self.clone().handle_control(executor.clone()).await {
-                    tracing::error!("Error sending control execution: {:?}", e);
+                match self.clone().handle_control(executor.clone()).await {
+                    Err(e) if e.is_malformed_message() => {}
+                    Err(e) => tracing::error!("Error sending control execution: {:?}", e),
+                    Ok(_) => {}
                 }
             }
         })
@@ -274,8 +494,7 @@ impl SealedServer {
         // main reply
         match request.kind() {
             JupyterMessageType::KernelInfoRequest => {
-                let info = executor.context.lock().await;
-                let cont = KernelInfoReply::build(info.language_info());
+                let cont = KernelInfoReply::build(&self.language_info, &request.header);
                 request.as_reply().with_content(cont)?.send_by(control).await?
             }
 
@@ -284,27 +503,40 @@ impl SealedServer {
                 request.as_reply().with_content(result)?.send_by(control).await?;
             }
             JupyterMessageType::InterruptRequest => {
-                let runner = executor.context.lock().await;
-                match runner.interrupt_kernel() {
-                    Some(_) => {
-                        request
-                            .as_reply()
-                            .create_message(JupyterMessageType::StatusReply)
-                            .with_content("ok")?
-                            .send_by(control)
-                            .await?
-                    }
-                    None => {
-                        request
-                            .as_reply()
-                            .create_message(JupyterMessageType::StatusReply)
-                            .with_content("ok")?
-                            .send_by(control)
-                            .await?
-                    }
+                self.interrupt(&executor.sockets);
+                request.as_reply().with_content(InterruptReply::default())?.send_by(control).await?
+            }
+            JupyterMessageType::ShutdownRequest => {
+                let task = request.recast::<ShutdownRequest>()?;
+                // queued requests will not run, neither in this kernel nor in the restarted one
+                let received = self.execution_order.abort_received();
+                // stop the running execution, otherwise the kernel lock is never released
+                self.interrupt(&executor.sockets);
+                // the replies of the aborted requests are sent before the kernel is reset, jupyter_client kills
+                // the kernel if it does not reply in 5 seconds, so a cell that ignores the interrupt is not waited for
+                let stopped = tokio::time::timeout(Duration::from_secs(3), async {
+                    self.execution_order.replied(received).await;
+                    executor.context.lock().await.shutdown(task.restart);
+                });
+                if stopped.await.is_err() {
+                    tracing::warn!("The running execution ignored the interrupt, shutting down without waiting for it");
+                }
+                // reset before replying, so that requests sent after the reply run in the restarted kernel
+                if task.restart {
+                    executor.sockets.reset();
+                    self.history.lock().await.restart();
+                }
+                request.as_reply().with_content(task.as_reply())?.send_by(control).await?;
+                // since protocol 5.4, the reply is also broadcast to all frontends
+                let broadcast = request.create_message(JupyterMessageType::ShutdownReply).with_content(task.as_reply())?;
+                {
+                    let iopub = &mut self.iopub.lock().await;
+                    broadcast.send_by(iopub).await?;
+                }
+                if !task.restart {
+                    self.signal_shutdown().await;
                 }
             }
-            JupyterMessageType::ShutdownRequest => self.signal_shutdown().await,
             JupyterMessageType::Custom(v) => {
                 tracing::error!("Got unknown control message: {:#?}", v);
             }
@@ -315,35 +547,6 @@ impl SealedServer {
         request.send_state(self.iopub.clone(), false).await?;
         Ok(())
     }
-    fn spawn_std_in<T>(self, executor: ExecuteProvider<T>) -> JoinHandle<()>
-    where
-        T: JupyterKernelProtocol + Send + 'static,
-    {
-        tokio::spawn(async move {
-            tracing::info!("IO Executor Spawned");
-            loop {
-                if let Err(e) = self.clone().handle_std_in(executor.clone()).await {
-                    tracing::error!("Error sending io execution: {:?}", e);
-                }
-            }
-        })
-    }
-    async fn handle_std_in<'a, T>(self, _: ExecuteProvider<T>) -> JupyterResult<()>
-    where
-        T: JupyterKernelProtocol + Send + 'static,
-    {
-        let io = &mut self.stdin.lock().await;
-        let request = JupyterMessage::read(io).await?;
-        match request.kind() {
-            JupyterMessageType::Custom(v) => {
-                tracing::error!("Got unknown io message: {:?}", v);
-            }
-            _ => {
-                tracing::warn!("Got custom io message: {:?}", request);
-            }
-        }
-        Ok(())
-    }
 }
 
 impl ShutdownReceiver {
@@ -352,9 +555,61 @@ impl ShutdownReceiver {
     }
 }
 
-async fn bind_socket<S: Socket>(config: &KernelControl, port: u16) -> JupyterResult<Connection<S>> {
-    let endpoint = format!("{}://{}:{}", config.transport, config.ip, port);
+/// Bind the socket and return the actual port, which is chosen by the system if `port` is `0`
+async fn bind_socket<S: Socket>(config: &KernelControl, port: u16) -> JupyterResult<(Connection<S>, u16)> {
+    let endpoint = config.endpoint(port)?;
     let mut socket = S::new();
-    socket.bind(&endpoint).await?;
-    Connection::new(socket, &config.key)
+    let port = match socket.bind(&endpoint).await? {
+        Endpoint::Tcp(_, port) => port,
+        _ => port,
+    };
+    let replay_window = Duration::from_secs(config.replay_window);
+    Ok((Connection::new(socket, config.signature_scheme.parse()?, &config.key, replay_window)?, port))
+}
+
+/// Send the connection info with bound ports to the launcher, and wait for its acknowledgement
+async fn register(config: &KernelControl, port: u16) -> JupyterResult<()> {
+    let mut socket = ReqSocket::new();
+    socket.connect(&config.endpoint(port)?).await?;
+    socket.send(ZmqMessage::from(serde_json::to_vec(config)?)).await?;
+    match tokio::time::timeout(Duration::from_secs(30), socket.recv()).await {
+        Ok(reply) => {
+            reply?;
+            Ok(())
+        }
+        Err(_) => Err(JupyterError::custom("The launcher did not acknowledge the registration")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abort_received_before_failure() {
+        let order = ExecutionOrder::default();
+        let (first, failed, queued) = (order.next(), order.next(), order.next());
+        assert!(!order.is_aborted(first));
+        // the failed cell aborts every request received so far, but not later ones
+        assert_eq!(order.abort_received(), queued);
+        assert!(order.is_aborted(failed));
+        assert!(order.is_aborted(queued));
+        let later = order.next();
+        assert!(!order.is_aborted(later));
+    }
+    #[tokio::test]
+    async fn replied_waits_for_the_number() {
+        let order = Arc::new(ExecutionOrder::default());
+        let waiting = tokio::spawn({
+            let order = order.clone();
+            async move { order.replied(2).await }
+        });
+        order.set_replied(1);
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+        order.set_replied(2);
+        tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+        // already replied
+        tokio::time::timeout(Duration::from_secs(1), order.replied(1)).await.unwrap();
+    }
 }
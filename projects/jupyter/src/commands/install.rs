@@ -1,4 +1,6 @@
 use super::*;
+use crate::JupyterError;
+use clap_derive::ValueEnum;
 use serde_json::to_string_pretty;
 
 /// To install/overwrite a new kernel to jupyter.
@@ -6,6 +8,20 @@ use serde_json::to_string_pretty;
 pub struct InstallAction {
     /// Optional name to operate on
     name: Option<String>,
+    /// How the frontend interrupts the kernel
+    #[arg(long, value_enum, default_value_t = InterruptMode::Message)]
+    interrupt_mode: InterruptMode,
+}
+
+/// How the frontend interrupts the kernel, see [kernel specs](https://jupyter-client.readthedocs.io/en/stable/kernels.html#kernel-specs)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum InterruptMode {
+    /// Send `interrupt_request` on the control channel
+    #[default]
+    Message,
+    /// Send `SIGINT` to the kernel process, which works even if the kernel is busy in a tight loop
+    Signal,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -13,7 +29,7 @@ struct KernelConfig {
     argv: Vec<String>,
     display_name: String,
     language: String,
-    interrupt_mode: String,
+    interrupt_mode: InterruptMode,
     metadata: Metadata,
 }
 
@@ -28,12 +44,15 @@ impl InstallAction {
     where
         T: JupyterKernelProtocol,
     {
-        do_install(&engine)
+        if cfg!(not(unix)) && self.interrupt_mode == InterruptMode::Signal {
+            return Err(JupyterError::custom("Signal interrupt mode is only supported on unix"));
+        }
+        do_install(&engine, self.interrupt_mode)
     }
 }
 
 impl KernelConfig {
-    pub fn new(language: &str, display: &str) -> JupyterResult<Self> {
+    pub fn new(language: &str, display: &str, interrupt_mode: InterruptMode, debugger: bool) -> JupyterResult<Self> {
         match std::env::current_exe() {
             Ok(path) => Ok(Self {
                 argv: vec![
@@ -44,8 +63,9 @@ impl KernelConfig {
                 ],
                 display_name: display.to_string(),
                 language: language.to_string(),
-                interrupt_mode: "message".to_string(),
-                metadata: Metadata { debugger: true },
+                interrupt_mode,
+                // jupyterlab enables the debugger by this flag of the kernelspec
+                metadata: Metadata { debugger },
             }),
             Err(e) => {
                 // "current exe path isn't valid UTF-8"
@@ -55,11 +75,11 @@ impl KernelConfig {
     }
 }
 
-pub(crate) fn do_install<T: JupyterKernelProtocol>(info: &T) -> JupyterResult<()> {
+pub(crate) fn do_install<T: JupyterKernelProtocol>(info: &T, interrupt_mode: InterruptMode) -> JupyterResult<()> {
     let info = info.language_info();
     let kernel_dir = get_kernel_dir(&info.language_key)?;
     std::fs::create_dir_all(&kernel_dir)?;
-    let kernel_config = KernelConfig::new(&info.language_key, &info.language)?;
+    let kernel_config = KernelConfig::new(&info.language_key, &info.language, interrupt_mode, info.debugger)?;
     let kernel_json = to_string_pretty(&kernel_config)?;
     let kernel_json_filename = kernel_dir.join("kernel.json");
     tracing::info!("Writing {}", kernel_json_filename.to_string_lossy());
@@ -1,9 +1,9 @@
 use super::*;
 
-use crate::{client::SealedServer, JupyterKernelProtocol};
+use crate::{client::SealedServer, JupyterError, JupyterKernelProtocol};
 
 use serde::{Deserialize, Serialize};
-use serde_json::from_str;
+use serde_json::{from_str, from_value, Map, Value};
 use std::{
     fs::read_to_string,
     path::{Path, PathBuf},
@@ -13,18 +13,34 @@ use std::{
 pub struct StartAction {
     #[arg(short = 'c', long = "control-file")]
     control_file: String,
+    /// Reject signed messages received again within this many seconds, `0` to accept replays
+    #[arg(long, default_value_t = 600)]
+    replay_window: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KernelControl {
+    // ports may be left out only if the launcher waits for the kernel to register, see `parse_control_file`
+    #[serde(default)]
     pub(crate) control_port: u16,
+    #[serde(default)]
     pub(crate) shell_port: u16,
+    #[serde(default)]
     pub(crate) stdin_port: u16,
+    #[serde(default)]
     pub(crate) hb_port: u16,
+    #[serde(default)]
     pub(crate) iopub_port: u16,
     pub(crate) transport: String,
     pub(crate) ip: String,
     pub(crate) key: String,
+    #[serde(default)]
+    pub(crate) signature_scheme: String,
+    /// The port of the launcher to send the bound ports, see [JEP 66](https://github.com/jupyter/enhancement-proposals/pull/66)
+    #[serde(default, skip_serializing)]
+    pub(crate) registration_port: Option<u16>,
+    #[serde(skip)]
+    pub(crate) replay_window: u64,
 }
 
 impl StartAction {
@@ -42,15 +58,121 @@ impl StartAction {
         // if let Err(error) = legacy_install::update_if_necessary() {
         //     eprintln!("Warning: tried to update client, but failed: {}", error);
         // }
-        SealedServer::run(&KernelControl::parse_control_file(&control_file)?, server)?;
+        let mut config = KernelControl::parse_control_file(&control_file)?;
+        config.replay_window = self.replay_window;
+        SealedServer::run(&config.with_ipc_ports(), server)?;
         Ok(())
     }
 }
 
 impl KernelControl {
+    const PORTS: [&'static str; 5] = ["control_port", "shell_port", "stdin_port", "hb_port", "iopub_port"];
+
     fn parse_control_file(file_name: &Path) -> JupyterResult<KernelControl> {
-        let control_file = read_to_string(file_name)?;
-        let object = from_str(&control_file)?;
-        Ok(object)
+        Self::parse(&read_to_string(file_name)?)
+    }
+    fn parse(control_file: &str) -> JupyterResult<KernelControl> {
+        let object: Map<String, Value> = from_str(control_file)?;
+        // a missing port binds a random one, which is only known to the launcher if the kernel registers
+        let registering = object.get("registration_port").is_some_and(|v| !v.is_null());
+        if !registering {
+            if let Some(missing) = Self::PORTS.iter().find(|key| !object.contains_key(**key)) {
+                return Err(JupyterError::custom(format!("Missing {} in the control file", missing)));
+            }
+        }
+        Ok(from_value(Value::Object(object))?)
+    }
+    /// Give each unset port of the ipc transport a distinct number, starting from `1` as jupyter_client does
+    pub(crate) fn with_ipc_ports(&self) -> KernelControl {
+        if self.transport != "ipc" {
+            return self.clone();
+        }
+        let mut ports = self.ports();
+        let mut next = 1;
+        for i in 0..ports.len() {
+            if ports[i] != 0 {
+                continue;
+            }
+            while ports.contains(&next) || self.ipc_path(next).exists() {
+                next += 1;
+            }
+            ports[i] = next;
+        }
+        let [control_port, shell_port, stdin_port, hb_port, iopub_port] = ports;
+        KernelControl { control_port, shell_port, stdin_port, hb_port, iopub_port, ..self.clone() }
+    }
+    /// The endpoint to bind the socket of `port`, ipc sockets are files named `{ip}-{port}`
+    pub(crate) fn endpoint(&self, port: u16) -> JupyterResult<String> {
+        match self.transport.as_str() {
+            "tcp" => Ok(format!("tcp://{}:{}", self.ip, port)),
+            "ipc" => Ok(format!("ipc://{}", self.ipc_path(port).display())),
+            s => Err(JupyterError::custom(format!("Unsupported transport: {}", s))),
+        }
+    }
+    fn ipc_path(&self, port: u16) -> PathBuf {
+        PathBuf::from(format!("{}-{}", self.ip, port))
+    }
+    fn ports(&self) -> [u16; 5] {
+        [self.control_port, self.shell_port, self.stdin_port, self.hb_port, self.iopub_port]
+    }
+    /// Remove the socket files of the ipc transport, left by a previous kernel or when this kernel shuts down
+    pub(crate) fn remove_ipc_sockets(&self) {
+        if self.transport != "ipc" {
+            return;
+        }
+        for port in self.ports() {
+            let path = self.ipc_path(port);
+            match std::fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => tracing::warn!("Could not remove socket file {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control(transport: &str, ip: &str) -> KernelControl {
+        let ports = serde_json::json!({
+            "control_port": 50160, "shell_port": 57503, "stdin_port": 52597, "hb_port": 42540, "iopub_port": 40885,
+            "transport": transport, "ip": ip, "key": "a0436f6c-1916-498b-8eb9-e81ab9368e84",
+        });
+        from_value(ports).unwrap()
+    }
+
+    #[test]
+    fn endpoint_by_transport() {
+        assert_eq!(control("tcp", "127.0.0.1").endpoint(57503).unwrap(), "tcp://127.0.0.1:57503");
+        assert_eq!(control("ipc", "/tmp/kernel-1").endpoint(3).unwrap(), "ipc:///tmp/kernel-1-3");
+        assert!(control("udp", "127.0.0.1").endpoint(57503).is_err());
+    }
+    #[test]
+    fn parse_requires_ports_unless_registering() {
+        let file = r#"{"transport": "tcp", "ip": "127.0.0.1", "key": "", "shell_port": 57503}"#;
+        assert!(KernelControl::parse(file).is_err());
+        let file = r#"{"transport": "tcp", "ip": "127.0.0.1", "key": "", "registration_port": 9000, "shell_port": 57503}"#;
+        let config = KernelControl::parse(file).unwrap();
+        assert_eq!(config.ports(), [0, 57503, 0, 0, 0]);
+        assert_eq!(config.registration_port, Some(9000));
+        let file = serde_json::to_string(&control("tcp", "127.0.0.1")).unwrap();
+        assert_eq!(KernelControl::parse(&file).unwrap().ports(), [50160, 57503, 52597, 42540, 40885]);
+    }
+
+    #[test]
+    fn ipc_ports_are_distinct() {
+        let dir = std::env::temp_dir().join(format!("jupyter-ipc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = control("ipc", &dir.join("kernel").display().to_string());
+        (config.control_port, config.shell_port, config.stdin_port, config.hb_port, config.iopub_port) = (0, 2, 0, 0, 0);
+        // a socket file left by another kernel is skipped
+        std::fs::write(config.ipc_path(3), b"").unwrap();
+        assert_eq!(config.with_ipc_ports().ports(), [1, 2, 4, 5, 6]);
+        // tcp ports are chosen when binding
+        let file = r#"{"transport": "tcp", "ip": "127.0.0.1", "key": "", "registration_port": 9000}"#;
+        assert_eq!(KernelControl::parse(file).unwrap().with_ipc_ports().ports(), [0; 5]);
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -5,32 +5,263 @@
 // or https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use crate::errors::JupyterResult;
-use hmac::{digest::KeyInit, Hmac};
-use sha2::Sha256;
+use crate::errors::{JupyterError, JupyterErrorKind, JupyterResult};
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use sha2::{Sha256, Sha384, Sha512};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::{Display, Formatter},
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 pub(crate) const KERNEL_JS: &[u8] = include_bytes!("../client/kernel.js");
 pub(crate) const LINT_JS: &[u8] = include_bytes!("../third_party/CodeMirror/addons/lint/lint.js");
 pub(crate) const LINT_CSS: &[u8] = include_bytes!("../third_party/CodeMirror/addons/lint/lint.css");
 pub(crate) const LINT_LICENSE: &[u8] = include_bytes!("../third_party/CodeMirror/LICENSE");
 
-pub(crate) type HmacSha256 = Hmac<Sha256>;
+/// The algorithm to sign messages, `signature_scheme` in the connection file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SignatureScheme {
+    /// `hmac-sha256`, used by all known frontends
+    #[default]
+    HmacSha256,
+    /// `hmac-sha384`
+    HmacSha384,
+    /// `hmac-sha512`
+    HmacSha512,
+    /// `hmac-md5`
+    HmacMd5,
+}
+
+/// The signer of messages, one variant per [`SignatureScheme`].
+#[derive(Clone, Debug)]
+pub(crate) enum JupyterMac {
+    Sha256(Hmac<Sha256>),
+    Sha384(Hmac<Sha384>),
+    Sha512(Hmac<Sha512>),
+    Md5(Hmac<Md5>),
+}
+
+/// The signatures of recently received messages, a signed message can only be accepted once.
+#[derive(Debug)]
+pub(crate) struct ReplayCache {
+    window: Duration,
+    seen: HashSet<Vec<u8>>,
+    order: VecDeque<(Instant, Vec<u8>)>,
+}
+
+/// The number of received messages rejected by a connection, reported by tracing.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct RejectedMessages {
+    bad_signature: u64,
+    replayed: u64,
+    malformed: u64,
+}
 
 #[derive(Debug)]
 pub struct Connection<S> {
     pub(crate) socket: S,
     /// Will be None if our key was empty (digest authentication disabled).
-    pub(crate) mac: Option<HmacSha256>,
+    pub(crate) mac: Option<JupyterMac>,
+    /// Will be None if messages are not signed, or the replay window is zero.
+    pub(crate) replay: Option<ReplayCache>,
+    pub(crate) rejected: RejectedMessages,
 }
 
 impl<S: zeromq::Socket> Connection<S> {
-    pub(crate) fn new(socket: S, key: &str) -> JupyterResult<Self> {
-        let mac = if key.is_empty() {
-            None
+    pub(crate) fn new(socket: S, scheme: SignatureScheme, key: &str, replay_window: Duration) -> JupyterResult<Self> {
+        let mac = JupyterMac::new(scheme, key)?;
+        let replay = match mac.is_some() && !replay_window.is_zero() {
+            true => Some(ReplayCache::new(replay_window)),
+            false => None,
+        };
+        Ok(Connection { socket, mac, replay, rejected: RejectedMessages::default() })
+    }
+}
+
+impl<S> Connection<S> {
+    /// Count the message dropped by `error`, other errors are ignored
+    pub(crate) fn count_rejected(&mut self, error: &JupyterError) {
+        let counter = &mut self.rejected;
+        match error.kind() {
+            JupyterErrorKind::BadSignature => counter.bad_signature += 1,
+            JupyterErrorKind::ReplayedMessage => counter.replayed += 1,
+            _ if error.is_malformed_message() => counter.malformed += 1,
+            _ => return,
         }
-        else {
-            Some(HmacSha256::new_from_slice(key.as_bytes()).expect("Shouldn't fail with HMAC"))
+        tracing::warn!(
+            bad_signature = counter.bad_signature,
+            replayed = counter.replayed,
+            malformed = counter.malformed,
+            "Rejected message: {}",
+            error
+        );
+    }
+}
+
+impl ReplayCache {
+    /// At most this many signatures are remembered, the oldest are forgotten first
+    const CAPACITY: usize = 65536;
+
+    pub(crate) fn new(window: Duration) -> Self {
+        Self { window, seen: HashSet::new(), order: VecDeque::new() }
+    }
+    /// Remember the signature, return `false` if it was seen in the window
+    pub(crate) fn insert(&mut self, signature: &[u8]) -> bool {
+        let now = Instant::now();
+        while let Some((time, _)) = self.order.front() {
+            if now.duration_since(*time) < self.window && self.order.len() < Self::CAPACITY {
+                break;
+            }
+            if let Some((_, old)) = self.order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+        if !self.seen.insert(signature.to_vec()) {
+            return false;
+        }
+        self.order.push_back((now, signature.to_vec()));
+        true
+    }
+}
+
+impl SignatureScheme {
+    /// The name in the connection file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureScheme::HmacSha256 => "hmac-sha256",
+            SignatureScheme::HmacSha384 => "hmac-sha384",
+            SignatureScheme::HmacSha512 => "hmac-sha512",
+            SignatureScheme::HmacMd5 => "hmac-md5",
+        }
+    }
+}
+
+impl FromStr for SignatureScheme {
+    type Err = JupyterError;
+
+    fn from_str(s: &str) -> JupyterResult<Self> {
+        match s {
+            // older connection files do not have this field
+            "" | "hmac-sha256" => Ok(SignatureScheme::HmacSha256),
+            "hmac-sha384" => Ok(SignatureScheme::HmacSha384),
+            "hmac-sha512" => Ok(SignatureScheme::HmacSha512),
+            "hmac-md5" => Ok(SignatureScheme::HmacMd5),
+            s => Err(JupyterErrorKind::UnknownSignatureScheme(s.to_string()).into()),
+        }
+    }
+}
+
+impl Display for SignatureScheme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl JupyterMac {
+    /// Create the signer of messages, `None` if the key is empty.
+    pub(crate) fn new(scheme: SignatureScheme, key: &str) -> JupyterResult<Option<Self>> {
+        if key.is_empty() {
+            return Ok(None);
+        }
+        let key = key.as_bytes();
+        let mac = match scheme {
+            SignatureScheme::HmacSha256 => Hmac::new_from_slice(key).map(JupyterMac::Sha256),
+            SignatureScheme::HmacSha384 => Hmac::new_from_slice(key).map(JupyterMac::Sha384),
+            SignatureScheme::HmacSha512 => Hmac::new_from_slice(key).map(JupyterMac::Sha512),
+            SignatureScheme::HmacMd5 => Hmac::new_from_slice(key).map(JupyterMac::Md5),
         };
-        Ok(Connection { socket, mac })
+        match mac {
+            Ok(o) => Ok(Some(o)),
+            Err(e) => Err(JupyterError::custom(format!("Invalid signing key: {}", e))),
+        }
+    }
+    /// The hex encoded signature of all parts
+    pub(crate) fn sign(&self, parts: &[Bytes]) -> String {
+        match self {
+            JupyterMac::Sha256(mac) => hex::encode(digest(mac.clone(), parts).finalize().into_bytes()),
+            JupyterMac::Sha384(mac) => hex::encode(digest(mac.clone(), parts).finalize().into_bytes()),
+            JupyterMac::Sha512(mac) => hex::encode(digest(mac.clone(), parts).finalize().into_bytes()),
+            JupyterMac::Md5(mac) => hex::encode(digest(mac.clone(), parts).finalize().into_bytes()),
+        }
+    }
+    /// Check the decoded signature of all parts, the comparison takes constant time
+    pub(crate) fn verify(&self, parts: &[Bytes], signature: &[u8]) -> bool {
+        match self {
+            JupyterMac::Sha256(mac) => digest(mac.clone(), parts).verify_slice(signature).is_ok(),
+            JupyterMac::Sha384(mac) => digest(mac.clone(), parts).verify_slice(signature).is_ok(),
+            JupyterMac::Sha512(mac) => digest(mac.clone(), parts).verify_slice(signature).is_ok(),
+            JupyterMac::Md5(mac) => digest(mac.clone(), parts).verify_slice(signature).is_ok(),
+        }
+    }
+}
+
+fn digest<M: Mac>(mut mac: M, parts: &[Bytes]) -> M {
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jupyter_message::{JupyterMessage, JupyterMessageType};
+    use zeromq::{DealerSocket, RouterSocket, Socket, SocketSend, ZmqMessage};
+
+    const KEY: &str = "7c2d0e4a-9f1b-4c3d-8e5f-6a7b8c9d0e1f";
+
+    #[test]
+    fn replay_insert_twice() {
+        let mut cache = ReplayCache::new(Duration::from_secs(60));
+        assert!(cache.insert(b"first"));
+        assert!(!cache.insert(b"first"));
+        assert!(cache.insert(b"second"));
+    }
+
+    #[test]
+    fn replay_insert_after_window() {
+        let mut cache = ReplayCache::new(Duration::from_millis(10));
+        assert!(cache.insert(b"first"));
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(cache.insert(b"first"));
+        assert!(!cache.insert(b"first"));
+    }
+
+    #[test]
+    fn replay_evict_at_capacity() {
+        let mut cache = ReplayCache::new(Duration::from_secs(60));
+        for i in 0..=ReplayCache::CAPACITY {
+            assert!(cache.insert(&i.to_le_bytes()));
+        }
+        assert_eq!(cache.order.len(), ReplayCache::CAPACITY);
+        assert_eq!(cache.seen.len(), ReplayCache::CAPACITY);
+        // the oldest is forgotten, the latest is still rejected
+        assert!(!cache.seen.contains(0usize.to_le_bytes().as_slice()));
+        assert!(!cache.insert(&ReplayCache::CAPACITY.to_le_bytes()));
+    }
+
+    #[tokio::test]
+    async fn read_counts_replayed() {
+        let mut router = RouterSocket::new();
+        let endpoint = router.bind("tcp://127.0.0.1:0").await.unwrap();
+        let mut connection = Connection::new(router, SignatureScheme::HmacSha256, KEY, Duration::from_secs(60)).unwrap();
+        let mut dealer = DealerSocket::new();
+        dealer.connect(&endpoint.to_string()).await.unwrap();
+
+        let request = JupyterMessage::default().create_message(JupyterMessageType::KernelInfoRequest);
+        let frames = request.encode(SignatureScheme::HmacSha256, KEY).unwrap();
+        for _ in 0..2 {
+            dealer.send(ZmqMessage::try_from(frames.clone()).unwrap()).await.unwrap();
+        }
+        let received = JupyterMessage::read(&mut connection).await.unwrap();
+        assert_eq!(received.header().msg_id, request.header().msg_id);
+        let error = JupyterMessage::read(&mut connection).await.unwrap_err();
+        assert!(matches!(error.kind(), JupyterErrorKind::ReplayedMessage));
+        assert_eq!(connection.rejected.replayed, 1);
+        assert_eq!(connection.rejected.bad_signature, 0);
     }
 }
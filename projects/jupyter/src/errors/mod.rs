@@ -21,6 +21,21 @@ pub struct JupyterError {
 }
 
 impl JupyterError {
+    /// Get the kind of the error.
+    pub fn kind(&self) -> &JupyterErrorKind {
+        &self.kind
+    }
+    /// Whether a received message was dropped because it is malformed or forged.
+    pub fn is_malformed_message(&self) -> bool {
+        matches!(
+            *self.kind,
+            JupyterErrorKind::MissingDelimiter
+                | JupyterErrorKind::BadSignature
+                | JupyterErrorKind::ReplayedMessage
+                | JupyterErrorKind::MissingParts(_)
+                | JupyterErrorKind::MalformedPart(..)
+        )
+    }
     /// Create a [JupyterErrorKind::Custom] error.
     pub fn custom<T: ToString>(message: T) -> Self {
         Self { kind: Box::new(JupyterErrorKind::Custom(message.to_string())) }
@@ -56,6 +71,22 @@ pub enum JupyterErrorKind {
     ChannelBlockage(&'static str),
     /// Zmq error.
     SubprocessTerminated(String),
+    /// The frontend does not support stdin for this execution.
+    StdinNotAllowed,
+    /// The execution was interrupted while waiting, e.g. for the input of the user.
+    Interrupted,
+    /// The `<IDS|MSG>` delimiter was not found in a received message.
+    MissingDelimiter,
+    /// The signature of a received message does not match, the message may be forged.
+    BadSignature,
+    /// A received message has fewer than four parts after the signature.
+    MissingParts(usize),
+    /// A part of a received message is not valid, with the part name and the reason.
+    MalformedPart(&'static str, String),
+    /// A signed message was received again, it may be replayed by an attacker.
+    ReplayedMessage,
+    /// The `signature_scheme` of the connection file is not supported.
+    UnknownSignatureScheme(String),
 }
 
 impl Error for JupyterErrorKind {}
@@ -82,6 +113,16 @@ impl Display for JupyterErrorKind {
             JupyterErrorKind::ExceptType(except_type) => write!(f, "Except type: {}", except_type),
             JupyterErrorKind::ChannelBlockage(channel) => write!(f, "Channel blockage: {}", channel),
             JupyterErrorKind::SubprocessTerminated(message) => write!(f, "Subprocess terminated: {}", message),
+            JupyterErrorKind::StdinNotAllowed => write!(f, "Stdin is not allowed by the frontend"),
+            JupyterErrorKind::Interrupted => write!(f, "Interrupted by the frontend"),
+            JupyterErrorKind::MissingDelimiter => write!(f, "Missing <IDS|MSG> delimiter"),
+            JupyterErrorKind::BadSignature => write!(f, "Bad message signature"),
+            JupyterErrorKind::ReplayedMessage => write!(f, "Replayed message"),
+            JupyterErrorKind::MissingParts(count) => write!(f, "Insufficient message parts: {}", count),
+            JupyterErrorKind::MalformedPart(part, reason) => write!(f, "Malformed {}: {}", part, reason),
+            JupyterErrorKind::UnknownSignatureScheme(scheme) => {
+                write!(f, "Unknown signature scheme `{}`, expect hmac-sha256, hmac-sha384, hmac-sha512 or hmac-md5", scheme)
+            }
         }
     }
 }
@@ -0,0 +1,324 @@
+use super::*;
+use crate::{
+    executor::sockets::JupyterKernelSockets,
+    jupyter_message::{CommMessage, CommOpen, JupyterMessage, JupyterMessageType},
+    widgets::run_deferred_callbacks,
+};
+use bytes::Bytes;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::MutexGuard,
+};
+use uuid::Uuid;
+
+/// The comm target name of the linter in the bundled `kernel.js`, which sends `{"code": ...}` when it opens a comm
+/// and waits for `{"problems": [...]}`.
+///
+/// The default target reports no problems, kernels with a linter register their own target under this name.
+pub const LINT_TARGET: &str = "evcxr-cargo-check";
+
+/// A comm opened between the kernel and the frontend
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct JupyterComm {
+    pub(crate) id: String,
+    pub(crate) target_name: String,
+}
+
+/// The kernel side handler of a comm target, see [`JupyterKernelSockets::register_comm_target`].
+///
+/// All callbacks are called on the shell channel, the returned data will be sent back to the frontend
+/// as a `comm_msg` of the same comm.
+///
+/// # Examples
+///
+/// ```rust, ignore
+/// struct CargoCheck;
+///
+/// impl JupyterCommTarget for CargoCheck {
+///     fn comm_open(&mut self, _: &JupyterComm, data: Value) -> Option<Value> {
+///         Some(json!({ "problems": [] }))
+///     }
+/// }
+///
+/// // replace the default target of the linter in bundled `kernel.js`
+/// sockets.register_comm_target(LINT_TARGET, CargoCheck);
+/// ```
+#[allow(unused_variables)]
+pub trait JupyterCommTarget: Send + 'static {
+    /// Called when the frontend opens a comm to this target
+    fn comm_open(&mut self, comm: &JupyterComm, data: Value) -> Option<Value> {
+        None
+    }
+    /// Called when the frontend sends a message to a comm of this target
+    fn comm_message(&mut self, comm: &JupyterComm, data: Value) -> Option<Value> {
+        None
+    }
+    /// Called when the frontend sends a message to a comm of this target, along with its binary buffers.
+    ///
+    /// The returned buffers are sent back with the data, the default implementation ignores all buffers
+    /// and calls [`JupyterCommTarget::comm_message`].
+    fn comm_message_with_buffers(&mut self, comm: &JupyterComm, data: Value, buffers: &[Bytes]) -> Option<(Value, Vec<Bytes>)> {
+        self.comm_message(comm, data).map(|v| (v, Vec::new()))
+    }
+    /// Called when the frontend closes a comm of this target
+    fn comm_close(&mut self, comm: &JupyterComm, data: Value) {}
+}
+
+/// All registered targets and opened comms.
+#[derive(Default)]
+pub(crate) struct CommRegistry {
+    targets: HashMap<String, Box<dyn JupyterCommTarget>>,
+    comms: BTreeMap<String, JupyterComm>,
+}
+
+/// The default [`LINT_TARGET`], answers every lint request so that the frontend does not wait forever
+#[derive(Debug)]
+struct NoLintTarget;
+
+enum CommDispatch {
+    /// The comm and the data and buffers to send back
+    Reply(JupyterComm, Option<(Value, Vec<Bytes>)>),
+    /// The id of the comm whose target does not exist
+    Reject(String),
+}
+
+impl JupyterCommTarget for NoLintTarget {
+    fn comm_open(&mut self, _: &JupyterComm, _: Value) -> Option<Value> {
+        Some(serde_json::json!({ "problems": [] }))
+    }
+}
+
+impl JupyterComm {
+    /// The unique id of the comm
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    /// The name of the target which handles the comm
+    pub fn target_name(&self) -> &str {
+        &self.target_name
+    }
+}
+
+impl JupyterKernelSockets {
+    /// Register a comm target, the frontend can open comms to it by name.
+    ///
+    /// A target with the same name will be replaced.
+    pub fn register_comm_target<S, T>(&self, target_name: S, target: T)
+    where
+        S: ToString,
+        T: JupyterCommTarget,
+    {
+        self.comm_registry().targets.insert(target_name.to_string(), Box::new(target));
+    }
+    /// Open a comm from the kernel side, the frontend must have registered the target.
+    pub async fn open_comm<S>(&self, target_name: S, data: Value, parent: &JupyterMessage) -> JupyterResult<JupyterComm>
+    where
+        S: ToString,
+    {
+        let comm = JupyterComm { id: Uuid::new_v4().simple().to_string(), target_name: target_name.to_string() };
+        self.open_comm_with(&comm, data, Value::Null, Vec::new(), parent).await?;
+        Ok(comm)
+    }
+    /// Open a comm with given id, message metadata and buffers
+    pub(crate) async fn open_comm_with(
+        &self,
+        comm: &JupyterComm,
+        data: Value,
+        metadata: Value,
+        buffers: Vec<Bytes>,
+        parent: &JupyterMessage,
+    ) -> JupyterResult<()> {
+        let content = CommOpen { comm_id: comm.id.clone(), target_name: comm.target_name.clone(), data };
+        let message = parent.create_message(JupyterMessageType::CommOpen).with_content(content)?.with_metadata(metadata)?;
+        self.send_io(message.with_buffers(buffers)).await?;
+        self.comm_registry().comms.insert(comm.id.clone(), comm.clone());
+        Ok(())
+    }
+    /// Send a message to the frontend through an opened comm.
+    pub async fn send_comm(&self, comm: &JupyterComm, data: Value, parent: &JupyterMessage) -> JupyterResult<()> {
+        self.send_comm_with_buffers(comm, data, Vec::new(), parent).await
+    }
+    /// Send a message with binary buffers to the frontend through an opened comm.
+    pub async fn send_comm_with_buffers(
+        &self,
+        comm: &JupyterComm,
+        data: Value,
+        buffers: Vec<Bytes>,
+        parent: &JupyterMessage,
+    ) -> JupyterResult<()> {
+        let content = CommMessage { comm_id: comm.id.clone(), data };
+        let message = parent.create_message(JupyterMessageType::CommMessage).with_content(content)?;
+        self.send_io(message.with_buffers(buffers)).await
+    }
+    /// Close an opened comm from the kernel side.
+    pub async fn close_comm(&self, comm: &JupyterComm, data: Value, parent: &JupyterMessage) -> JupyterResult<()> {
+        self.comm_registry().comms.remove(&comm.id);
+        let content = CommMessage { comm_id: comm.id.clone(), data };
+        self.send_io(parent.create_message(JupyterMessageType::CommClose).with_content(content)?).await
+    }
+    /// Register the default [`LINT_TARGET`], called once when the kernel starts
+    pub(crate) fn register_lint_target(&self) {
+        self.register_comm_target(LINT_TARGET, NoLintTarget);
+    }
+    /// Forget all opened comms, the registered targets are kept
+    pub(crate) fn clear_comms(&self) {
+        self.comm_registry().comms.clear();
+    }
+    /// All opened comms, in the form of `comm_id => target_name`
+    pub(crate) fn comm_info(&self) -> BTreeMap<String, String> {
+        self.comm_registry().comms.values().map(|v| (v.id.clone(), v.target_name.clone())).collect()
+    }
+    /// Dispatch `comm_open`, `comm_msg` and `comm_close` from the frontend to the registered targets
+    pub(crate) async fn handle_comm(&self, request: &JupyterMessage) -> JupyterResult<()> {
+        let dispatch = self.dispatch_comm(request);
+        // widget callbacks may read the registries, which are unlocked now
+        run_deferred_callbacks();
+        match dispatch? {
+            CommDispatch::Reply(comm, Some((data, buffers))) => {
+                self.send_comm_with_buffers(&comm, data, buffers, request).await
+            }
+            CommDispatch::Reply(_, None) => Ok(()),
+            // the spec requires to close the comm immediately if the target does not exist
+            CommDispatch::Reject(comm_id) => {
+                let content = CommMessage { comm_id, data: Value::Null };
+                self.send_io(request.create_message(JupyterMessageType::CommClose).with_content(content)?).await
+            }
+        }
+    }
+    /// Call the target of the comm without holding the registry across `.await`
+    fn dispatch_comm(&self, request: &JupyterMessage) -> JupyterResult<CommDispatch> {
+        let mut registry = self.comm_registry();
+        if let JupyterMessageType::CommOpen = request.kind() {
+            let task = request.recast::<CommOpen>()?;
+            let comm = JupyterComm { id: task.comm_id, target_name: task.target_name };
+            return match registry.targets.get_mut(&comm.target_name) {
+                Some(target) => {
+                    let reply = target.comm_open(&comm, task.data).map(|v| (v, Vec::new()));
+                    registry.comms.insert(comm.id.clone(), comm.clone());
+                    Ok(CommDispatch::Reply(comm, reply))
+                }
+                None => {
+                    tracing::warn!("No comm target named {}", comm.target_name);
+                    Ok(CommDispatch::Reject(comm.id))
+                }
+            };
+        }
+        let task = request.recast::<CommMessage>()?;
+        let closing = matches!(request.kind(), JupyterMessageType::CommClose);
+        let comm = match closing {
+            true => registry.comms.remove(&task.comm_id),
+            false => registry.comms.get(&task.comm_id).cloned(),
+        };
+        let comm = match comm {
+            Some(comm) => comm,
+            None => {
+                tracing::warn!("No comm with id {}", task.comm_id);
+                return Ok(CommDispatch::Reply(JupyterComm { id: task.comm_id, target_name: String::new() }, None));
+            }
+        };
+        let reply = match registry.targets.get_mut(&comm.target_name) {
+            Some(target) if closing => {
+                target.comm_close(&comm, task.data);
+                None
+            }
+            Some(target) => target.comm_message_with_buffers(&comm, task.data, request.buffers()),
+            None => None,
+        };
+        Ok(CommDispatch::Reply(comm, reply))
+    }
+    fn comm_registry(&self) -> MutexGuard<'_, CommRegistry> {
+        // a panic in one target should not break all other comms
+        self.comms.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    pub(crate) async fn send_io(&self, message: JupyterMessage) -> JupyterResult<()> {
+        match &self.io_channel {
+            Some(channel) => {
+                let io = &mut channel.lock().await;
+                message.send_by(io).await
+            }
+            None => Err(JupyterError::custom("Missing IO channel")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::SignatureScheme;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the calls and echoes the data of messages
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl JupyterCommTarget for Recorder {
+        fn comm_open(&mut self, comm: &JupyterComm, data: Value) -> Option<Value> {
+            self.0.lock().unwrap().push(format!("open {} {}", comm.id, data));
+            None
+        }
+        fn comm_message(&mut self, comm: &JupyterComm, data: Value) -> Option<Value> {
+            self.0.lock().unwrap().push(format!("message {} {}", comm.id, data));
+            Some(data)
+        }
+        fn comm_close(&mut self, comm: &JupyterComm, data: Value) {
+            self.0.lock().unwrap().push(format!("close {} {}", comm.id, data));
+        }
+    }
+
+    fn request(msg_type: &str, content: Value) -> JupyterMessage {
+        let header = serde_json::json!({ "msg_id": "1", "msg_type": msg_type, "session": "s", "version": "5.3" });
+        let frames = ["client", "<IDS|MSG>", "", &header.to_string(), "{}", "{}", &content.to_string()];
+        JupyterMessage::decode(
+            frames.into_iter().map(|v| Bytes::from(v.to_string())).collect(),
+            SignatureScheme::HmacSha256,
+            "",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn dispatch_open_message_close() {
+        let sockets = JupyterKernelSockets::default();
+        let calls = Arc::new(Mutex::new(vec![]));
+        sockets.register_comm_target("recorder", Recorder(calls.clone()));
+
+        let open = request("comm_open", serde_json::json!({ "comm_id": "c1", "target_name": "recorder", "data": 1 }));
+        assert!(matches!(sockets.dispatch_comm(&open).unwrap(), CommDispatch::Reply(_, None)));
+        assert_eq!(sockets.comm_info().get("c1").map(String::as_str), Some("recorder"));
+
+        let message = request("comm_msg", serde_json::json!({ "comm_id": "c1", "data": 2 }));
+        match sockets.dispatch_comm(&message).unwrap() {
+            CommDispatch::Reply(comm, Some((data, buffers))) => {
+                assert_eq!(comm.id, "c1");
+                assert_eq!(data, 2);
+                assert!(buffers.is_empty());
+            }
+            _ => panic!("Expected a reply to comm_msg"),
+        }
+
+        let close = request("comm_close", serde_json::json!({ "comm_id": "c1", "data": 3 }));
+        assert!(matches!(sockets.dispatch_comm(&close).unwrap(), CommDispatch::Reply(_, None)));
+        assert!(sockets.comm_info().is_empty());
+        assert_eq!(*calls.lock().unwrap(), ["open c1 1", "message c1 2", "close c1 3"]);
+    }
+
+    #[test]
+    fn dispatch_unknown_target() {
+        let sockets = JupyterKernelSockets::default();
+        let open = request("comm_open", serde_json::json!({ "comm_id": "c1", "target_name": "missing", "data": {} }));
+        assert!(matches!(sockets.dispatch_comm(&open).unwrap(), CommDispatch::Reject(id) if id == "c1"));
+        assert!(sockets.comm_info().is_empty());
+    }
+
+    #[test]
+    fn dispatch_default_lint_target() {
+        let sockets = JupyterKernelSockets::default();
+        sockets.register_lint_target();
+        let open =
+            request("comm_open", serde_json::json!({ "comm_id": "c1", "target_name": LINT_TARGET, "data": { "code": "1" } }));
+        match sockets.dispatch_comm(&open).unwrap() {
+            CommDispatch::Reply(_, Some((data, _))) => assert_eq!(data, serde_json::json!({ "problems": [] })),
+            _ => panic!("Expected the lint target to answer"),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::*;
+use crate::{
+    executor::sockets::JupyterKernelSockets,
+    jupyter_message::{ClearOutput, DisplayData, JupyterMessage, JupyterMessageType},
+};
+use uuid::Uuid;
+
+/// A displayed output which can be updated in place, e.g. progress bars.
+///
+/// # Examples
+///
+/// ```rust, ignore
+/// let progress = self.sockets.display("0%", &code.header).await?;
+/// for i in 1..=100 {
+///     self.sockets.update_display(&progress, format!("{}%", i), &code.header).await?;
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DisplayHandle {
+    display_id: String,
+}
+
+impl DisplayHandle {
+    /// Refer to a display by id, all outputs with the same id are updated together
+    pub fn new<S>(display_id: S) -> Self
+    where
+        S: ToString,
+    {
+        Self { display_id: display_id.to_string() }
+    }
+    /// The id of the display in `transient`
+    pub fn display_id(&self) -> &str {
+        &self.display_id
+    }
+}
+
+impl JupyterKernelSockets {
+    /// Show the executed value as a new output, the returned handle can update it later.
+    ///
+    /// Cell counter will not +1
+    pub async fn display(&self, executed: impl Executed, parent: &JupyterMessage) -> JupyterResult<DisplayHandle> {
+        let handle = DisplayHandle::new(Uuid::new_v4().simple());
+        self.display_with(&handle, executed, parent).await?;
+        Ok(handle)
+    }
+    /// Show the executed value as a new output of given display
+    pub async fn display_with(
+        &self,
+        handle: &DisplayHandle,
+        executed: impl Executed,
+        parent: &JupyterMessage,
+    ) -> JupyterResult<()> {
+        let content = DisplayData::new(&executed, &handle.display_id);
+        let message = parent.create_message(JupyterMessageType::DisplayData).with_content(content)?;
+        self.send_output(message.with_buffers(executed.as_buffers())).await
+    }
+    /// Replace all outputs of the display with the executed value
+    pub async fn update_display(
+        &self,
+        handle: &DisplayHandle,
+        executed: impl Executed,
+        parent: &JupyterMessage,
+    ) -> JupyterResult<()> {
+        let content = DisplayData::new(&executed, &handle.display_id);
+        let message = parent.create_message(JupyterMessageType::UpdateDisplayData).with_content(content)?;
+        self.send_output(message.with_buffers(executed.as_buffers())).await
+    }
+    /// Clear the outputs of the cell, wait until new output is available if `wait` is `true`
+    pub async fn clear_output(&self, wait: bool, parent: &JupyterMessage) -> JupyterResult<()> {
+        self.send_output(parent.create_message(JupyterMessageType::ClearOutput).with_content(ClearOutput { wait })?).await
+    }
+}
@@ -1,15 +1,39 @@
 #![allow(deprecated)]
+use crate::{JupyterError, JupyterErrorKind};
+use jupyter_types::{Executed, JupyterContext};
 use serde::{
     ser::{SerializeMap, SerializeStruct},
     Serialize, Serializer,
 };
+use serde_json::{Map, Value};
 
 /// The request to execute code
 #[derive(Clone, Debug)]
 pub struct ExecutionReply {
     success: bool,
+    aborted: bool,
     execution_count: usize,
     payload: Vec<ExecutionPayload>,
+    user_expressions: Map<String, Value>,
+    error: Option<ExecutionError>,
+}
+
+/// The error raised by the executed code, shown as a red traceback in the frontend.
+///
+/// # Examples
+///
+/// ```rust, ignore
+/// let error = ExecutionError::new("TypeError", "mismatched types")
+///     .with_traceback("  --> src/lib.rs:2:9")
+///     .with_traceback("\x1b[0;34m  = note:\x1b[0m expected `i32`, found `&str`");
+/// ExecutionReply::new(false).with_error(error)
+/// ```
+#[derive(Clone, Debug)]
+pub struct ExecutionError {
+    ename: String,
+    evalue: String,
+    traceback: Vec<String>,
+    colored: bool,
 }
 
 /// The result of executing code
@@ -38,6 +62,11 @@ impl Serialize for ExecutionReply {
         S: Serializer,
     {
         let mut map = serializer.serialize_struct("ExecutionReply", 5)?;
+        if self.aborted {
+            map.serialize_field("status", "aborted")?;
+            map.serialize_field("execution_count", &self.execution_count)?;
+            return map.end();
+        }
         match self.success {
             true => map.serialize_field("status", "ok")?,
             false => map.serialize_field("status", "error")?,
@@ -46,6 +75,28 @@ impl Serialize for ExecutionReply {
         if !self.payload.is_empty() {
             map.serialize_field("payload", &self.payload)?;
         }
+        if self.success {
+            map.serialize_field("user_expressions", &self.user_expressions)?;
+        }
+        else {
+            let error = self.error.clone().unwrap_or_else(|| ExecutionError::new("Error", ""));
+            map.serialize_field("ename", &error.ename)?;
+            map.serialize_field("evalue", &error.evalue)?;
+            map.serialize_field("traceback", &error.traceback())?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for ExecutionError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_struct("ExecutionError", 3)?;
+        map.serialize_field("ename", &self.ename)?;
+        map.serialize_field("evalue", &self.evalue)?;
+        map.serialize_field("traceback", &self.traceback())?;
         map.end()
     }
 }
@@ -77,7 +128,11 @@ impl Serialize for ExecutionPayload {
 impl ExecutionReply {
     /// Create a new execution reply
     pub fn new(success: bool) -> Self {
-        Self { success, execution_count: 0, payload: vec![] }
+        Self { success, aborted: false, execution_count: 0, payload: vec![], user_expressions: Map::new(), error: None }
+    }
+    /// Create a reply for the request which was not executed, due to an error of previous request
+    pub fn aborted() -> Self {
+        Self { aborted: true, ..Self::new(false) }
     }
     /// Set the execution count
     pub fn with_count(self, count: usize) -> Self {
@@ -88,4 +143,99 @@ impl ExecutionReply {
         self.payload.push(payload);
         self
     }
+    /// Add the result of a user expression, see [`JupyterKernelProtocol::evaluate_expression`]
+    ///
+    /// [`JupyterKernelProtocol::evaluate_expression`]: crate::JupyterKernelProtocol::evaluate_expression
+    pub fn with_expression<S>(mut self, name: S, result: Result<Box<dyn Executed>, ExecutionError>) -> Self
+    where
+        S: ToString,
+    {
+        let mut bundle = Map::new();
+        match result {
+            Ok(o) => {
+                let mut data = Map::new();
+                data.insert(o.mime_type(), o.as_json(&JupyterContext::default()));
+                bundle.insert("status".to_string(), Value::from("ok"));
+                bundle.insert("data".to_string(), Value::Object(data));
+                bundle.insert("metadata".to_string(), Value::Object(Map::new()));
+            }
+            Err(e) => {
+                bundle.insert("status".to_string(), Value::from("error"));
+                bundle.insert("ename".to_string(), Value::from(e.ename.as_str()));
+                bundle.insert("evalue".to_string(), Value::from(e.evalue.as_str()));
+                bundle.insert("traceback".to_string(), Value::from(e.traceback()));
+            }
+        }
+        self.user_expressions.insert(name.to_string(), Value::Object(bundle));
+        self
+    }
+    /// Mark the execution as failed with the error
+    pub fn with_error<E>(self, error: E) -> Self
+    where
+        E: Into<ExecutionError>,
+    {
+        Self { success: false, error: Some(error.into()), ..self }
+    }
+    /// Whether the code was executed without error
+    pub fn is_success(&self) -> bool {
+        self.success && !self.aborted
+    }
+    /// The error of a failed execution
+    pub fn error(&self) -> Option<&ExecutionError> {
+        self.error.as_ref()
+    }
+}
+
+impl ExecutionError {
+    /// Create a new error with the error name and message
+    pub fn new<N, V>(ename: N, evalue: V) -> Self
+    where
+        N: ToString,
+        V: ToString,
+    {
+        Self { ename: ename.to_string(), evalue: evalue.to_string(), traceback: vec![], colored: true }
+    }
+    /// Append a line to the traceback, ANSI escape codes are rendered by the frontend
+    pub fn with_traceback<S>(mut self, line: S) -> Self
+    where
+        S: ToString,
+    {
+        self.traceback.push(line.to_string());
+        self
+    }
+    /// Whether to highlight the `ename: evalue` line in red, `true` by default
+    pub fn with_color(self, colored: bool) -> Self {
+        Self { colored, ..self }
+    }
+    /// The name of the error, e.g. `TypeError`
+    pub fn ename(&self) -> &str {
+        &self.ename
+    }
+    /// The message of the error
+    pub fn evalue(&self) -> &str {
+        &self.evalue
+    }
+    /// All traceback lines, led by the `ename: evalue` line
+    pub fn traceback(&self) -> Vec<String> {
+        let head = match self.colored {
+            true => format!("\x1b[0;31m{}\x1b[0m: {}", self.ename, self.evalue),
+            false => format!("{}: {}", self.ename, self.evalue),
+        };
+        std::iter::once(head).chain(self.traceback.iter().cloned()).collect()
+    }
+}
+
+impl From<JupyterError> for ExecutionError {
+    fn from(error: JupyterError) -> Self {
+        match error.kind() {
+            JupyterErrorKind::Interrupted => ExecutionError::new("KeyboardInterrupt", ""),
+            _ => ExecutionError::new("JupyterError", error),
+        }
+    }
+}
+
+impl From<ExecutionError> for ExecutionReply {
+    fn from(error: ExecutionError) -> Self {
+        ExecutionReply::new(false).with_error(error)
+    }
 }
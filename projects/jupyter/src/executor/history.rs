@@ -0,0 +1,268 @@
+use crate::jupyter_message::{HistoryAccess, HistoryEntry};
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+/// The execution history of the kernel, optionally persisted as JSON lines
+#[derive(Debug, Default)]
+pub(crate) struct JupyterHistory {
+    session: usize,
+    entries: Vec<HistoryEntry>,
+    file: Option<PathBuf>,
+}
+
+impl JupyterHistory {
+    /// Load the history of previous sessions from `file` and start a new session
+    pub fn new(file: Option<PathBuf>) -> Self {
+        let mut entries = vec![];
+        if let Some(path) = &file {
+            match File::open(path) {
+                Ok(o) => {
+                    for line in BufReader::new(o).lines().map_while(Result::ok) {
+                        match serde_json::from_str::<HistoryEntry>(&line) {
+                            Ok(o) => entries.push(o),
+                            Err(e) => tracing::warn!("Skip broken history line: {}", e),
+                        }
+                    }
+                }
+                Err(e) => tracing::info!("No history loaded from {}: {}", path.display(), e),
+            }
+        }
+        let session = entries.iter().map(|v| v.session).max().unwrap_or(0) + 1;
+        Self { session, entries, file }
+    }
+    /// Start a new session after the kernel restarted, previous sessions are kept
+    pub fn restart(&mut self) {
+        self.session += 1;
+    }
+    /// Record a line of the current session
+    pub fn push(&mut self, line: usize, input: String) {
+        let entry = HistoryEntry { session: self.session, line, input };
+        if let Some(path) = &self.file {
+            if let Err(e) = append_line(path, &entry) {
+                tracing::warn!("Failed to persist history to {}: {}", path.display(), e);
+            }
+        }
+        self.entries.push(entry);
+    }
+    /// Answer a history request
+    pub fn query(&self, access: &HistoryAccess) -> Vec<HistoryEntry> {
+        match access {
+            HistoryAccess::Range { session, start, stop } => {
+                let session = match *session {
+                    s if s > 0 => s as usize,
+                    s => self.session.saturating_sub(s.unsigned_abs() as usize),
+                };
+                self.entries
+                    .iter()
+                    .filter(|v| v.session == session && v.line >= *start && stop.is_none_or(|stop| v.line < stop))
+                    .cloned()
+                    .collect()
+            }
+            HistoryAccess::Tail { n } => last_n(self.entries.to_vec(), *n),
+            HistoryAccess::Search { pattern, n, unique } => {
+                let mut seen = HashSet::new();
+                let mut found: Vec<HistoryEntry> = self
+                    .entries
+                    .iter()
+                    .rev()
+                    .filter(|v| glob_match(pattern, &v.input))
+                    .filter(|v| !*unique || seen.insert(v.input.as_str()))
+                    .cloned()
+                    .collect();
+                found.reverse();
+                last_n(found, *n)
+            }
+        }
+    }
+}
+
+fn append_line(path: &PathBuf, entry: &HistoryEntry) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+}
+
+fn last_n(mut entries: Vec<HistoryEntry>, n: Option<usize>) -> Vec<HistoryEntry> {
+    if let Some(n) = n {
+        let skip = entries.len().saturating_sub(n);
+        entries.drain(..skip);
+    }
+    entries
+}
+
+/// Match with sqlite `GLOB` semantics, which IPython uses for history search
+///
+/// Only the last `*` is remembered for backtracking, so the time is `O(pattern × text)`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let tokens = glob_tokens(pattern);
+    let text: Vec<char> = text.chars().collect();
+    let (mut t, mut p) = (0, 0);
+    // the token after the last `*`, and the text position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match tokens.get(p) {
+            Some(GlobToken::Star) => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(token) if token.matches(text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // let the `*` eat one more char
+                Some((after, at)) => {
+                    star = Some((after, at + 1));
+                    p = after;
+                    t = at + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[p..].iter().all(|v| matches!(v, GlobToken::Star))
+}
+
+enum GlobToken {
+    Star,
+    Any,
+    Char(char),
+    Class { negate: bool, ranges: Vec<(char, char)> },
+}
+
+impl GlobToken {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            GlobToken::Star => true,
+            GlobToken::Any => true,
+            GlobToken::Char(v) => *v == c,
+            GlobToken::Class { negate, ranges } => ranges.iter().any(|(lo, hi)| *lo <= c && c <= *hi) != *negate,
+        }
+    }
+}
+
+fn glob_tokens(pattern: &str) -> Vec<GlobToken> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < pattern.len() {
+        let token = match pattern[i] {
+            '*' => GlobToken::Star,
+            '?' => GlobToken::Any,
+            // an unclosed or empty `[` matches itself
+            '[' => match pattern[i + 1..].iter().position(|c| *c == ']') {
+                Some(end) if end > 0 => {
+                    let class = &pattern[i + 1..i + 1 + end];
+                    i += end + 1;
+                    let (negate, class) = match class[0] {
+                        '^' | '!' => (true, &class[1..]),
+                        _ => (false, class),
+                    };
+                    let mut ranges = vec![];
+                    let mut j = 0;
+                    while j < class.len() {
+                        if j + 2 < class.len() && class[j + 1] == '-' {
+                            ranges.push((class[j], class[j + 2]));
+                            j += 3;
+                        }
+                        else {
+                            ranges.push((class[j], class[j]));
+                            j += 1;
+                        }
+                    }
+                    GlobToken::Class { negate, ranges }
+                }
+                _ => GlobToken::Char('['),
+            },
+            c => GlobToken::Char(c),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(lines: &[(usize, usize, &str)]) -> JupyterHistory {
+        let entries = lines.iter().map(|(session, line, input)| HistoryEntry {
+            session: *session,
+            line: *line,
+            input: input.to_string(),
+        });
+        JupyterHistory { session: 3, entries: entries.collect(), file: None }
+    }
+
+    fn inputs(entries: Vec<HistoryEntry>) -> Vec<String> {
+        entries.into_iter().map(|v| v.input).collect()
+    }
+
+    #[test]
+    fn glob_star() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("let *", "let a = 1"));
+        assert!(glob_match("*print*", "println!(\"{}\", a)"));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(!glob_match("a*b*c", "aXXbYY"));
+        assert!(!glob_match("a*", "ba"));
+        // backtracking stays linear in the number of stars
+        let text = "a".repeat(10000);
+        assert!(!glob_match("*a*a*a*a*a*a*a*a*a*a*a*a*b", &text));
+    }
+
+    #[test]
+    fn glob_any() {
+        assert!(glob_match("?", "x"));
+        assert!(!glob_match("?", ""));
+        assert!(!glob_match("?", "xy"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("?*?", "ab"));
+    }
+
+    #[test]
+    fn glob_class() {
+        assert!(glob_match("[abc]", "b"));
+        assert!(!glob_match("[abc]", "d"));
+        assert!(glob_match("x[0-9]", "x7"));
+        assert!(!glob_match("x[0-9]", "xa"));
+        assert!(glob_match("[!0-9]*", "a1"));
+        assert!(!glob_match("[!0-9]*", "1a"));
+        assert!(glob_match("[^a]", "b"));
+        // unclosed brackets are literal
+        assert!(glob_match("[a", "[a"));
+        assert!(glob_match("[]", "[]"));
+    }
+
+    #[test]
+    fn search_unique() {
+        let history = history(&[(1, 1, "a = 1"), (1, 2, "b = 2"), (2, 1, "a = 1"), (3, 1, "c = 3")]);
+        let all = HistoryAccess::Search { pattern: "? = *".to_string(), n: None, unique: false };
+        assert_eq!(inputs(history.query(&all)), ["a = 1", "b = 2", "a = 1", "c = 3"]);
+        let unique = HistoryAccess::Search { pattern: "? = *".to_string(), n: None, unique: true };
+        // the latest of the duplicates is kept
+        assert_eq!(inputs(history.query(&unique)), ["b = 2", "a = 1", "c = 3"]);
+        let last = HistoryAccess::Search { pattern: "*".to_string(), n: Some(2), unique: true };
+        assert_eq!(inputs(history.query(&last)), ["a = 1", "c = 3"]);
+    }
+
+    #[test]
+    fn range_session() {
+        let history = history(&[(1, 1, "a"), (2, 1, "b"), (2, 2, "c"), (2, 3, "d"), (3, 1, "e")]);
+        let range = |session, start, stop| history.query(&HistoryAccess::Range { session, start, stop });
+        assert_eq!(inputs(range(2, 1, None)), ["b", "c", "d"]);
+        assert_eq!(inputs(range(2, 2, Some(3))), ["c"]);
+        // non positive sessions count back from the current one
+        assert_eq!(inputs(range(0, 1, None)), ["e"]);
+        assert_eq!(inputs(range(-1, 2, None)), ["c", "d"]);
+        assert_eq!(inputs(range(-2, 1, None)), ["a"]);
+        assert!(range(-5, 1, None).is_empty());
+    }
+}
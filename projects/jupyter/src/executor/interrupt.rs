@@ -0,0 +1,55 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tokio::sync::Notify;
+
+/// Signals that the frontend asked to interrupt the running execution, see [`ExecutionRequest::interrupt`].
+///
+/// Long running kernels should check [`InterruptToken::is_interrupted`] in tight loops, or race their work
+/// against [`InterruptToken::interrupted`], then return an error reply.
+///
+/// # Examples
+///
+/// ```rust, ignore
+/// for item in items {
+///     if code.interrupt.is_interrupted() {
+///         return ExecutionReply::new(false).with_error(ExecutionError::new("KeyboardInterrupt", ""));
+///     }
+///     process(item);
+/// }
+/// ```
+///
+/// [`ExecutionRequest::interrupt`]: crate::ExecutionRequest::interrupt
+#[derive(Clone, Debug, Default)]
+pub struct InterruptToken {
+    interrupted: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl InterruptToken {
+    /// Whether the execution has been interrupted
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Acquire)
+    }
+    /// Wait until the execution is interrupted
+    pub async fn interrupted(&self) {
+        loop {
+            // register before checking, so that an interrupt between them is not missed
+            let notified = self.notify.notified();
+            if self.is_interrupted() {
+                return;
+            }
+            notified.await;
+        }
+    }
+    /// Interrupt the running execution, wake up all waiters
+    pub(crate) fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Release);
+        self.notify.notify_waiters();
+    }
+    /// Clear the flag before a new execution starts
+    pub(crate) fn reset(&self) {
+        self.interrupted.store(false, Ordering::Release);
+    }
+}
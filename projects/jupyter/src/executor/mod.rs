@@ -1,15 +1,21 @@
+pub mod comms;
+pub mod display;
 pub mod execution_reply;
+pub(crate) mod history;
+pub mod interrupt;
 pub mod sockets;
 
 use crate::{
     executor::sockets::JupyterConnection,
     value_type::{InspectModule, InspectVariable, InspectVariableRequest},
-    ExecutionReply, ExecutionRequest, ExecutionResult, JupyterError, JupyterResult,
+    CompletionReply, ExecutionError, ExecutionReply, ExecutionRequest, ExecutionResult, IsCompleteReply, JupyterError,
+    JupyterResult,
 };
 use jupyter_types::Executed;
 use std::{
     fmt::{Debug, Formatter},
     future::Future,
+    path::PathBuf,
     sync::Arc,
 };
 use tokio::sync::Mutex;
@@ -26,8 +32,25 @@ pub trait JupyterKernelProtocol: Send + Sync + 'static {
     /// since Generator is not stable, we use sender instead
     ///
     /// `Generator<Yield = dyn Executed, Return = ExecutionReply>`
+    ///
+    /// Return [`ExecutionReply::with_error`] to show an error with traceback instead of a result.
+    ///
+    /// Requests are executed one by one in the order they arrive, watch [`ExecutionRequest::interrupt`] to stop early.
     fn running(&mut self, code: ExecutionRequest) -> impl Future<Output = ExecutionReply> + Send;
 
+    /// Evaluate an expression of `user_expressions` after the code executed successfully, e.g. for variable watchers.
+    ///
+    /// *Return an error by default, which will be shown as the result of the expression.*
+    fn evaluate_expression(&mut self, expression: &str) -> Result<Box<dyn Executed>, ExecutionError> {
+        Err(ExecutionError::new("NotImplementedError", "`JupyterKernelProtocol::evaluate_expression` is not yet implemented."))
+    }
+
+    /// Release resources before the kernel exits, such as flushing files and killing child processes.
+    ///
+    /// If `restart` is `true`, the kernel keeps running and should reset all its state instead, the execution counter,
+    /// comms and widgets are reset by the server.
+    fn shutdown(&mut self, restart: bool) {}
+
     /// Show the running time of the code.
     ///
     /// - unit: seconds
@@ -37,6 +60,45 @@ pub trait JupyterKernelProtocol: Send + Sync + 'static {
         format!("<sub>Elapsed time: {:.2} seconds.</sub>", time)
     }
 
+    /// Provide completion candidates when the user presses `Tab`.
+    ///
+    /// # Arguments
+    ///
+    /// - `code`: The code of the current cell.
+    /// - `cursor_pos`: The cursor position in `code`, counted in unicode code points.
+    ///
+    /// *Return no matches by default, the frontend will keep the code unchanged.*
+    fn complete(&self, code: &str, cursor_pos: usize) -> CompletionReply {
+        CompletionReply::new(cursor_pos, cursor_pos)
+    }
+
+    /// The file to persist the execution history across sessions, in JSON lines format.
+    ///
+    /// *Return `None` to keep the history of current session in memory only, which is the default.*
+    fn history_file(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Check whether the code is ready to execute, console frontends ask this when `Enter` is pressed.
+    ///
+    /// *Return [`IsCompleteReply::Unknown`] by default, the frontend will decide by itself.*
+    fn is_complete(&self, code: &str) -> IsCompleteReply {
+        IsCompleteReply::Unknown
+    }
+
+    /// Show the help of the object under the cursor, triggered by `Shift+Tab` or hover tooltips.
+    ///
+    /// # Arguments
+    ///
+    /// - `code`: The code of the current cell.
+    /// - `cursor_pos`: The cursor position in `code`, counted in unicode code points.
+    /// - `detail_level`: `0` for a short summary, `1` for the full documentation or source.
+    ///
+    /// *Return `None` if nothing was found under the cursor.*
+    fn inspect_code(&self, code: &str, cursor_pos: usize, detail_level: u8) -> Option<Box<dyn Executed>> {
+        None
+    }
+
     /// Inspect the variables on right side.
     ///
     /// # Arguments
@@ -77,11 +139,8 @@ pub trait JupyterKernelProtocol: Send + Sync + 'static {
         "`JupyterKernelProtocol::inspect_sources` is not yet implemented.".to_string()
     }
 
-    /// Query the currently loaded modules
-    ///
-    /// # Arguments
-    ///
-    /// * `total`: The number of modules to be loaded, 0 means unlimited.
+    /// Never called, the kernel is locked by the running execution when the interrupt arrives.
+    #[deprecated(note = "watch `ExecutionRequest::interrupt` or `JupyterKernelSockets::is_interrupted` instead")]
     fn interrupt_kernel(&self) -> Option<String> {
         None
     }
@@ -114,6 +173,15 @@ pub struct LanguageInfo {
     pub highlighter: String,
     /// Notebook exporter
     pub exporter: String,
+    /// The banner shown when a console starts
+    pub banner: String,
+    /// The links shown in the help menu, in the form of `(text, url)`
+    pub help_links: Vec<(String, String)>,
+    /// Whether the kernel supports the debug requests, such as the variable inspector
+    pub debugger: bool,
+    /// The optional protocol features supported by the kernel, see
+    /// [JEP 92](https://github.com/jupyter/enhancement-proposals/blob/master/92-jupyter-optional-features/jupyter-optional-features.md)
+    pub supported_features: Vec<String>,
 }
 
 impl LanguageInfo {
@@ -134,6 +202,10 @@ impl LanguageInfo {
             lexer: "rust".to_string(),
             highlighter: "rust".to_string(),
             exporter: "rust".to_string(),
+            banner: format!("Jupyter Server Protocol v{} in Rust", env!("CARGO_PKG_VERSION")),
+            help_links: vec![],
+            debugger: true,
+            supported_features: vec![],
         }
     }
     /// Set the language file extensions and mimetype
@@ -165,4 +237,34 @@ impl LanguageInfo {
         self.version = version.to_string();
         self
     }
+    /// Set the banner shown when a console starts
+    pub fn with_banner<T>(mut self, banner: T) -> Self
+    where
+        T: ToString,
+    {
+        self.banner = banner.to_string();
+        self
+    }
+    /// Add a link to the help menu
+    pub fn with_help_link<T, S>(mut self, text: T, url: S) -> Self
+    where
+        T: ToString,
+        S: ToString,
+    {
+        self.help_links.push((text.to_string(), url.to_string()));
+        self
+    }
+    /// Set whether the kernel supports the debug requests
+    pub fn with_debugger(mut self, debugger: bool) -> Self {
+        self.debugger = debugger;
+        self
+    }
+    /// Declare an optional protocol feature supported by the kernel, e.g. `kernel subshells`
+    pub fn with_feature<T>(mut self, feature: T) -> Self
+    where
+        T: ToString,
+    {
+        self.supported_features.push(feature.to_string());
+        self
+    }
 }
@@ -1,12 +1,18 @@
 use super::*;
 use crate::{
     connection::Connection,
-    jupyter_message::{JupyterMessage, JupyterMessageType},
+    executor::{comms::CommRegistry, execution_reply::ExecutionError, interrupt::InterruptToken},
+    jupyter_message::{InputReply, InputRequest, JupyterMessage, JupyterMessageType},
+    widgets::WidgetRegistry,
+    JupyterErrorKind,
 };
 use jupyter_types::{Executed, JupyterContext};
 use serde::Serialize;
-use std::path::PathBuf;
-use zeromq::PubSocket;
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use zeromq::{PubSocket, RouterSocket};
 
 /// Indicates successful establishment of link with jupyter frontend
 #[derive(Debug)]
@@ -20,10 +26,15 @@ pub struct JupyterConnection {
 /// The sockets for Jupyter kernel.
 #[derive(Clone, Default)]
 pub struct JupyterKernelSockets {
-    // Need to start from 1, otherwise `*` will be displayed
     pub(crate) io_channel: Option<Arc<Mutex<Connection<PubSocket>>>>,
-    pub(crate) execute_count: Arc<Mutex<usize>>,
+    pub(crate) stdin_channel: Option<Arc<Mutex<Connection<RouterSocket>>>>,
+    /// The count of the latest non-silent execution, the first cell is `1`
+    pub(crate) execute_count: Arc<AtomicUsize>,
     pub(crate) debugging: Arc<Mutex<bool>>,
+    pub(crate) silent: Arc<AtomicBool>,
+    pub(crate) interrupt: InterruptToken,
+    pub(crate) comms: Arc<std::sync::Mutex<CommRegistry>>,
+    pub(crate) widgets: WidgetRegistry,
 }
 
 impl Debug for JupyterKernelSockets {
@@ -57,9 +68,9 @@ impl JupyterStream {
 }
 
 impl JupyterKernelSockets {
-    /// Send an executed result.
+    /// Send an executed result, numbered with the count of the running cell.
     ///
-    /// Cell counter will be +1
+    /// See [`JupyterKernelSockets::display`] for outputs without count
     pub async fn send_executed(&self, executed: impl Executed, parent: &JupyterMessage) {
         self.try_send_executed(executed, parent).await.ok();
     }
@@ -69,22 +80,70 @@ impl JupyterKernelSockets {
     pub async fn send_stream(&self, stream: JupyterStream, parent: &JupyterMessage) {
         self.try_send_io_stream(stream, parent).await.ok();
     }
+    /// Send an error with traceback, the frontend renders it in red.
+    ///
+    /// Cell counter will not +1
+    pub async fn send_error(&self, error: &ExecutionError, parent: &JupyterMessage) -> JupyterResult<()> {
+        self.send_output(parent.create_message(JupyterMessageType::Error).with_content(error)?).await
+    }
+    /// Ask the user to enter a line in the frontend, and wait for the input.
+    ///
+    /// Fails with [`JupyterErrorKind::StdinNotAllowed`] if the frontend does not support input for this execution,
+    /// or [`JupyterErrorKind::Interrupted`] if the execution is interrupted before the user answers.
+    pub async fn read_line(&self, prompt: &str, password: bool, parent: &ExecutionRequest) -> JupyterResult<String> {
+        if !parent.allow_stdin {
+            return Err(JupyterErrorKind::StdinNotAllowed.into());
+        }
+        let channel = match &self.stdin_channel {
+            Some(o) => o,
+            None => return Err(JupyterError::custom("Missing stdin channel")),
+        };
+        // the stdin socket of the frontend shares the identity with its shell socket
+        let request = parent
+            .header
+            .as_reply()
+            .with_message_type(JupyterMessageType::InputRequest)
+            .with_content(InputRequest { prompt: prompt.to_string(), password })?;
+        let stdin = &mut channel.lock().await;
+        request.send_by(stdin).await?;
+        loop {
+            // reading is cancel safe, no message is lost if the interrupt wins
+            let reply = tokio::select! {
+                biased;
+                _ = parent.interrupt.interrupted() => return Err(JupyterErrorKind::Interrupted.into()),
+                reply = JupyterMessage::read(stdin) => reply,
+            };
+            let reply = match reply {
+                Ok(o) => o,
+                Err(e) if e.is_malformed_message() => continue,
+                Err(e) => return Err(e),
+            };
+            match reply.kind() {
+                JupyterMessageType::InputReply if reply.parent_header.msg_id == request.header.msg_id => {
+                    return Ok(reply.recast::<InputReply>()?.value);
+                }
+                _ => tracing::warn!("Got unexpected stdin message: {:?}", reply),
+            }
+        }
+    }
     /// Read counter
     pub fn get_counter(&self) -> usize {
-        match self.execute_count.try_lock() {
-            Ok(o) => *o,
-            Err(_) => 0,
-        }
+        self.execute_count.load(Ordering::SeqCst)
     }
     /// reset counter
     pub fn set_counter(&self, count: usize) -> bool {
-        match self.execute_count.try_lock() {
-            Ok(mut o) => {
-                *o = count;
-                true
-            }
-            Err(_) => false,
-        }
+        self.execute_count.store(count, Ordering::SeqCst);
+        true
+    }
+    /// Advance the counter for a new non-silent execution, and return its count
+    pub(crate) fn next_counter(&self) -> usize {
+        self.execute_count.fetch_add(1, Ordering::SeqCst) + 1
+    }
+    /// Reset the counter and forget all comms and widgets, called when the kernel restarts
+    pub(crate) fn reset(&self) {
+        self.set_counter(0);
+        self.clear_comms();
+        self.widgets.lock().unwrap_or_else(|e| e.into_inner()).clear();
     }
     /// Get current debug state
     pub fn get_debug_mode(&self) -> bool {
@@ -104,21 +163,41 @@ impl JupyterKernelSockets {
         }
     }
 
+    /// Whether the running execution has been interrupted, by `interrupt_request` or `SIGINT`
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupt.is_interrupted()
+    }
+    /// Wait until the running execution is interrupted, race it against the work to cancel cooperatively
+    pub async fn interrupted(&self) {
+        self.interrupt.interrupted().await
+    }
+    /// Whether the current execution is silent, see [`ExecutionRequest::silent`]
+    pub fn is_silent(&self) -> bool {
+        self.silent.load(Ordering::Acquire)
+    }
+    pub(crate) fn set_silent(&self, silent: bool) {
+        self.silent.store(silent, Ordering::Release)
+    }
+    /// Send an output on iopub, unless the current execution is silent
+    pub(crate) async fn send_output(&self, message: JupyterMessage) -> JupyterResult<()> {
+        match self.is_silent() {
+            true => Ok(()),
+            false => self.send_io(message).await,
+        }
+    }
+
     async fn try_send_executed(&self, executed: impl Executed, parent: &JupyterMessage) -> JupyterResult<()> {
+        if self.is_silent() {
+            return Ok(());
+        }
         let data = ExecutionResult::default().with_data(executed.mime_type(), executed.as_json(&JupyterContext::default()));
         match &self.io_channel {
             Some(channel) => {
-                let counter = match self.execute_count.try_lock() {
-                    Ok(mut o) => {
-                        let old = *o;
-                        *o += 1;
-                        old
-                    }
-                    Err(_) => 0,
-                };
+                let counter = self.get_counter();
                 parent
                     .as_reply()
                     .with_content(data.with_count(counter))?
+                    .with_buffers(executed.as_buffers())
                     .with_message_type(JupyterMessageType::ExecuteResult)
                     .send_by(&mut &mut channel.lock().await)
                     .await
@@ -128,6 +207,9 @@ impl JupyterKernelSockets {
     }
 
     async fn try_send_io_stream(&self, stream: JupyterStream, parent: &JupyterMessage) -> JupyterResult<()> {
+        if self.is_silent() {
+            return Ok(());
+        }
         match &self.io_channel {
             Some(channel) => {
                 parent
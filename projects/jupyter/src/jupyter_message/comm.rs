@@ -0,0 +1,23 @@
+use super::*;
+
+/// The content of `comm_open`, sent by either side
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommOpen {
+    /// The unique id of the comm
+    pub comm_id: String,
+    /// The name of the target which handles the comm
+    pub target_name: String,
+    /// The initial data of the comm
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// The content of `comm_msg` and `comm_close`, sent by either side
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommMessage {
+    /// The unique id of the comm
+    pub comm_id: String,
+    /// The payload of the message
+    #[serde(default)]
+    pub data: Value,
+}
@@ -2,22 +2,22 @@ use super::*;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommonInfoRequest {
-    pub target_name: String,
+    #[serde(default)]
+    pub target_name: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 pub struct CommonInfoReply {
-    success: bool,
+    comms: BTreeMap<String, String>,
 }
 
-#[allow(unused)]
 impl CommonInfoRequest {
-    pub fn as_reply(&self) -> CommonInfoReply {
-        CommonInfoReply { success: true }
-    }
-
-    pub fn as_error(&self) -> CommonInfoReply {
-        CommonInfoReply { success: false }
+    /// Create the reply with all opened comms, in the form of `comm_id => target_name`
+    pub fn as_reply(&self, comms: BTreeMap<String, String>) -> CommonInfoReply {
+        match &self.target_name {
+            Some(target) => CommonInfoReply { comms: comms.into_iter().filter(|(_, v)| v == target).collect() },
+            None => CommonInfoReply { comms },
+        }
     }
 }
 
@@ -26,8 +26,15 @@ impl Serialize for CommonInfoReply {
     where
         S: Serializer,
     {
+        let mut comms = Map::new();
+        for (id, target) in &self.comms {
+            let mut info = Map::new();
+            info.insert("target_name".to_string(), Value::String(target.clone()));
+            comms.insert(id.clone(), Value::Object(info));
+        }
         let mut s = serializer.serialize_map(Some(2))?;
-        s.serialize_entry("status", if self.success { "ok" } else { "error" })?;
+        s.serialize_entry("status", "ok")?;
+        s.serialize_entry("comms", &comms)?;
         s.end()
     }
 }
@@ -0,0 +1,106 @@
+use super::*;
+
+/// The request to complete code at the cursor
+#[derive(Clone, Debug, Deserialize)]
+pub struct CompletionRequest {
+    /// The code context in which completion is requested
+    pub code: String,
+    /// The cursor position within `code` where completion is requested, in unicode code points
+    pub cursor_pos: usize,
+}
+
+/// The reply of a code completion request
+#[derive(Clone, Debug)]
+pub struct CompletionReply {
+    matches: Vec<CompletionItem>,
+    cursor_start: usize,
+    cursor_end: usize,
+}
+
+/// A completion candidate, see [`CompletionReply::with_item`]
+#[derive(Clone, Debug)]
+pub struct CompletionItem {
+    text: String,
+    typing: String,
+    signature: String,
+}
+
+impl Serialize for CompletionReply {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_struct("CompletionReply", 5)?;
+        map.serialize_field("status", "ok")?;
+        map.serialize_field("matches", &self.matches.iter().map(|v| v.text.as_str()).collect::<Vec<_>>())?;
+        map.serialize_field("cursor_start", &self.cursor_start)?;
+        map.serialize_field("cursor_end", &self.cursor_end)?;
+        let mut metadata = Map::new();
+        if self.matches.iter().any(|v| !v.typing.is_empty() || !v.signature.is_empty()) {
+            let typed: Vec<Value> = self.matches.iter().map(|v| v.as_typed(self.cursor_start, self.cursor_end)).collect();
+            metadata.insert("_jupyter_types_experimental".to_string(), Value::Array(typed));
+        }
+        map.serialize_field("metadata", &metadata)?;
+        map.end()
+    }
+}
+
+impl CompletionReply {
+    /// Create a new completion reply which replaces the text between `cursor_start` and `cursor_end`
+    pub fn new(cursor_start: usize, cursor_end: usize) -> Self {
+        Self { matches: vec![], cursor_start, cursor_end }
+    }
+    /// Add a completion candidate to the reply
+    pub fn with_item<T>(mut self, item: T) -> Self
+    where
+        T: Into<CompletionItem>,
+    {
+        self.matches.push(item.into());
+        self
+    }
+}
+
+impl CompletionItem {
+    /// Create a new completion candidate
+    pub fn new<T>(text: T) -> Self
+    where
+        T: ToString,
+    {
+        Self { text: text.to_string(), typing: String::new(), signature: String::new() }
+    }
+    /// Set the kind of the candidate, e.g. `function`, `keyword`, `module`
+    pub fn with_type<T>(self, typing: T) -> Self
+    where
+        T: ToString,
+    {
+        Self { typing: typing.to_string(), ..self }
+    }
+    /// Set the signature of the candidate, e.g. `fn(x: i32) -> i32`
+    pub fn with_signature<T>(self, signature: T) -> Self
+    where
+        T: ToString,
+    {
+        Self { signature: signature.to_string(), ..self }
+    }
+    fn as_typed(&self, start: usize, end: usize) -> Value {
+        let mut map = Map::new();
+        map.insert("start".to_string(), Value::from(start));
+        map.insert("end".to_string(), Value::from(end));
+        map.insert("text".to_string(), Value::from(self.text.as_str()));
+        map.insert("type".to_string(), Value::from(self.typing.as_str()));
+        map.insert("signature".to_string(), Value::from(self.signature.as_str()));
+        Value::Object(map)
+    }
+}
+
+impl From<&str> for CompletionItem {
+    fn from(value: &str) -> Self {
+        CompletionItem::new(value)
+    }
+}
+
+impl From<String> for CompletionItem {
+    fn from(value: String) -> Self {
+        Self { text: value, typing: String::new(), signature: String::new() }
+    }
+}
@@ -18,8 +18,7 @@ impl<'de> Deserialize<'de> for JupyterMessageType {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        let out = JupyterMessageType::from_str(s.as_str());
-        unsafe { Ok(out.unwrap_unchecked()) }
+        Ok(JupyterMessageType::new(s.as_str()))
     }
 }
 
@@ -43,11 +42,12 @@ impl<'de> Visitor<'de> for JupyterMessageHeaderVisitor {
         A: MapAccess<'de>,
     {
         let mut date = Utc::now();
-        let mut msg_id = Uuid::nil();
-        let mut session = Uuid::nil();
+        let mut msg_id = String::new();
+        let mut session = String::new();
         let mut msg_type = JupyterMessageType::default();
-        while let Some(key) = map.next_key()? {
-            match key {
+        let mut extra = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
                 "date" => {
                     let rfc3339 = map.next_value::<String>()?;
                     if let Ok(o) = DateTime::parse_from_rfc3339(&rfc3339) {
@@ -55,28 +55,18 @@ impl<'de> Visitor<'de> for JupyterMessageHeaderVisitor {
                     }
                 }
                 "msg_type" => msg_type = map.next_value()?,
-                "msg_id" => {
-                    let v4 = map.next_value::<String>()?;
-                    let head = v4.split('_').next().unwrap_or("");
-                    if let Ok(o) = Uuid::parse_str(head) {
-                        msg_id = o;
-                    }
-                }
-                "session" => {
-                    let v4 = map.next_value::<String>()?;
-                    let head = v4.split('_').next().unwrap_or("");
-                    if let Ok(o) = Uuid::parse_str(head) {
-                        session = o;
-                    }
-                }
+                // ids are opaque, e.g. `<uuid>_<pid>_<n>` of jupyterlab
+                "msg_id" => msg_id = map.next_value()?,
+                "session" => session = map.next_value()?,
                 "username" => self.username = map.next_value()?,
                 "version" => self.version = map.next_value()?,
                 _ => {
-                    print!("Unknown key {}", key)
+                    let value = map.next_value::<Value>()?;
+                    extra.insert(key, value);
                 }
             }
         }
-        Ok(JupyterMessageHeader { date, msg_id, msg_type, session, username: self.username, version: self.version })
+        Ok(JupyterMessageHeader { date, msg_id, msg_type, session, username: self.username, version: self.version, extra })
     }
 }
 
@@ -0,0 +1,34 @@
+use super::*;
+use jupyter_types::{Executed, JupyterContext};
+
+/// The content of `display_data` and `update_display_data`
+#[derive(Clone, Debug, Serialize)]
+pub struct DisplayData {
+    /// The rendered data keyed by mime type
+    pub data: BTreeMap<String, Value>,
+    /// The metadata of the data
+    pub metadata: Map<String, Value>,
+    /// The data which should not be persisted to the notebook, e.g. `display_id`
+    pub transient: Map<String, Value>,
+}
+
+/// The content of `clear_output`
+#[derive(Clone, Debug, Serialize)]
+pub struct ClearOutput {
+    /// Wait to clear the output until new output is available
+    pub wait: bool,
+}
+
+impl DisplayData {
+    /// Render the executed value, and attach it to the display
+    pub fn new<T>(executed: &T, display_id: &str) -> Self
+    where
+        T: Executed + ?Sized,
+    {
+        let mut data = BTreeMap::new();
+        data.insert(executed.mime_type(), executed.as_json(&JupyterContext::default()));
+        let mut transient = Map::new();
+        transient.insert("display_id".to_string(), Value::from(display_id));
+        Self { data, metadata: Map::new(), transient }
+    }
+}
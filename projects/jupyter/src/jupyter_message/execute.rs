@@ -1,4 +1,5 @@
 use super::*;
+use crate::executor::interrupt::InterruptToken;
 use jupyter_types::{Executed, JupyterContext};
 
 /// The result of executing code
@@ -33,15 +34,16 @@ impl Default for ExecutionResult {
 pub struct ExecutionRequest {
     /// The code to execute
     pub code: String,
-    /// Whether to execute the code as quietly as possible
+    /// Whether to execute the code as quietly as possible, no output will be published
     pub silent: bool,
     /// Whether to store history
     pub store_history: bool,
     /// A mapping of names to expressions to be evaluated in the user's dict.
     pub allow_stdin: bool,
-    /// A mapping of names to expressions to be evaluated in the user's dict.
+    /// Whether to abort the queued execute requests if this one fails
     pub stop_on_error: bool,
-    /// A mapping of names to expressions to be evaluated in the user's dict.
+    /// A mapping of names to expressions to be evaluated after the code executed
+    #[serde(default)]
     pub user_expressions: Value,
     /// A mapping of names to expressions to be evaluated in the user's dict.
     #[serde(skip_deserializing)]
@@ -49,9 +51,28 @@ pub struct ExecutionRequest {
     /// Specify which request the execution results should be attached to
     #[serde(skip_deserializing)]
     pub header: JupyterMessage,
+    /// The metadata of the request, e.g. `cellId`, `deletedCells` and `recordTiming` of jupyterlab
+    #[serde(skip_deserializing)]
+    pub metadata: Map<String, Value>,
+    /// Triggered when the frontend interrupts this execution
+    #[serde(skip_deserializing)]
+    pub interrupt: InterruptToken,
+}
+
+/// The code being executed, broadcast to all frontends
+#[derive(Clone, Debug, Serialize)]
+pub struct ExecutionInput {
+    /// The code being executed
+    pub code: String,
+    /// The counter of this execution
+    pub execution_count: usize,
 }
 
 impl ExecutionRequest {
+    /// The id of the notebook cell being executed, if the frontend sends one
+    pub fn cell_id(&self) -> Option<&str> {
+        self.metadata.get("cellId").and_then(Value::as_str)
+    }
     /// Create a new execution request
     pub fn as_reply(&self, success: bool, count: usize) -> ExecutionReply {
         ExecutionReply::new(success).with_count(count)
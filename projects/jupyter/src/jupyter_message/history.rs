@@ -0,0 +1,97 @@
+use super::*;
+
+/// The request to query the execution history
+#[derive(Clone, Debug, Deserialize)]
+pub struct HistoryRequest {
+    /// Whether to return the output of each execution as well
+    #[serde(default)]
+    pub output: bool,
+    /// Which part of the history to query
+    #[serde(flatten)]
+    pub access: HistoryAccess,
+}
+
+/// The `hist_access_type` of a history request
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "hist_access_type", rename_all = "lowercase")]
+pub enum HistoryAccess {
+    /// Get lines `start..stop` of a session
+    Range {
+        /// Positive for an absolute session number, `0` or negative for relative to the current session
+        #[serde(default)]
+        session: i64,
+        /// The first line to return, inclusive
+        #[serde(default)]
+        start: usize,
+        /// The last line to return, exclusive, `None` means until the end
+        #[serde(default)]
+        stop: Option<usize>,
+    },
+    /// Get the last `n` lines
+    Tail {
+        /// The number of lines to return
+        #[serde(default)]
+        n: Option<usize>,
+    },
+    /// Get the lines matching a glob pattern
+    Search {
+        /// Glob pattern, `*` matches any sequence and `?` matches any single character
+        #[serde(default)]
+        pattern: String,
+        /// Only return the last `n` matches
+        #[serde(default)]
+        n: Option<usize>,
+        /// Skip duplicate inputs, only the latest one is kept
+        #[serde(default)]
+        unique: bool,
+    },
+}
+
+/// A line of code executed by the kernel
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The session number, increase by one every time the kernel starts
+    pub session: usize,
+    /// The execution count in the session
+    pub line: usize,
+    /// The code executed
+    pub input: String,
+}
+
+/// The reply of a history request
+#[derive(Clone, Debug)]
+pub struct HistoryReply {
+    output: bool,
+    history: Vec<HistoryEntry>,
+}
+
+impl HistoryRequest {
+    /// Create the reply with the history found
+    pub fn as_reply(&self, history: Vec<HistoryEntry>) -> HistoryReply {
+        HistoryReply { output: self.output, history }
+    }
+}
+
+impl Serialize for HistoryReply {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("status", "ok")?;
+        let history: Vec<Value> = self
+            .history
+            .iter()
+            .map(|entry| {
+                let input = match self.output {
+                    // output is not recorded, send `(input, null)` as IPython does
+                    true => Value::Array(vec![Value::from(entry.input.as_str()), Value::Null]),
+                    false => Value::from(entry.input.as_str()),
+                };
+                Value::Array(vec![Value::from(entry.session), Value::from(entry.line), input])
+            })
+            .collect();
+        map.serialize_entry("history", &history)?;
+        map.end()
+    }
+}
@@ -0,0 +1,17 @@
+use super::*;
+
+/// The request to read a line from the frontend, sent on the stdin channel
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputRequest {
+    /// The text to show before the input box
+    pub prompt: String,
+    /// Whether to hide the typed text, e.g. for passwords
+    pub password: bool,
+}
+
+/// The line entered by the user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputReply {
+    /// The text entered, without the trailing newline
+    pub value: String,
+}
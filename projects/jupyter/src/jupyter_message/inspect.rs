@@ -0,0 +1,50 @@
+use super::*;
+use jupyter_types::{Executed, JupyterContext};
+
+/// The request to show the help of the object under the cursor
+#[derive(Clone, Debug, Deserialize)]
+pub struct InspectionRequest {
+    /// The code context in which introspection is requested
+    pub code: String,
+    /// The cursor position within `code` where introspection is requested, in unicode code points
+    pub cursor_pos: usize,
+    /// The level of detail desired, `0` is equivalent to `x?` and `1` is equivalent to `x??` in IPython
+    #[serde(default)]
+    pub detail_level: u8,
+}
+
+/// The reply of an introspection request
+#[derive(Clone, Debug)]
+pub struct InspectionReply {
+    found: bool,
+    data: BTreeMap<String, Value>,
+    metadata: Map<String, Value>,
+}
+
+impl Serialize for InspectionReply {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("InspectionReply", 4)?;
+        state.serialize_field("status", "ok")?;
+        state.serialize_field("found", &self.found)?;
+        state.serialize_field("data", &self.data)?;
+        state.serialize_field("metadata", &self.metadata)?;
+        state.end()
+    }
+}
+
+impl InspectionRequest {
+    /// Create the reply from the object found by the kernel
+    pub fn as_reply<T>(&self, found: Option<&T>) -> InspectionReply
+    where
+        T: Executed + ?Sized,
+    {
+        let mut data = BTreeMap::new();
+        if let Some(executed) = found {
+            data.insert(executed.mime_type(), executed.as_json(&JupyterContext::default()));
+        }
+        InspectionReply { found: found.is_some(), data, metadata: Map::new() }
+    }
+}
@@ -1 +1,16 @@
+use super::*;
 
+/// The content of `interrupt_reply`, the interrupt is always accepted
+#[derive(Clone, Debug, Default)]
+pub struct InterruptReply {}
+
+impl Serialize for InterruptReply {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("InterruptReply", 1)?;
+        s.serialize_field("status", "ok")?;
+        s.end()
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+
+/// The request to check whether the code is ready to execute
+#[derive(Clone, Debug, Deserialize)]
+pub struct IsCompleteRequest {
+    /// The code entered so far as a multiline string
+    pub code: String,
+}
+
+/// Whether the code is ready to execute, used by console frontends when `Enter` is pressed
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IsCompleteReply {
+    /// The code is ready to execute
+    Complete,
+    /// The code is not finished, a newline with the given indent will be inserted
+    Incomplete {
+        /// The indent of the next line, e.g. four spaces
+        indent: String,
+    },
+    /// The code will typically be sent for execution, so that the user sees the error soonest
+    Invalid,
+    /// The kernel is not able to determine the completeness
+    #[default]
+    Unknown,
+}
+
+impl IsCompleteReply {
+    /// Create an incomplete reply with the indent of the next line
+    pub fn incomplete<S>(indent: S) -> Self
+    where
+        S: ToString,
+    {
+        Self::Incomplete { indent: indent.to_string() }
+    }
+}
+
+impl Serialize for IsCompleteReply {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        match self {
+            Self::Complete => map.serialize_entry("status", "complete")?,
+            Self::Incomplete { indent } => {
+                map.serialize_entry("status", "incomplete")?;
+                map.serialize_entry("indent", indent)?;
+            }
+            Self::Invalid => map.serialize_entry("status", "invalid")?,
+            Self::Unknown => map.serialize_entry("status", "unknown")?,
+        }
+        map.end()
+    }
+}
@@ -1,6 +1,14 @@
 use super::*;
 use crate::LanguageInfo;
 
+/// The highest version of the message protocol implemented by this crate
+pub(crate) const PROTOCOL_VERSION: (u32, u32) = (5, 5);
+
+/// The [`PROTOCOL_VERSION`] in the form of the `version` header field
+pub(crate) fn protocol_version() -> String {
+    format!("{}.{}", PROTOCOL_VERSION.0, PROTOCOL_VERSION.1)
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct KernelInfoReply {
     status: String,
@@ -11,6 +19,8 @@ pub struct KernelInfoReply {
     debugger: bool,
     banner: String,
     help_links: Vec<HelpLink>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    supported_features: Option<Vec<String>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -32,27 +42,33 @@ pub struct HelpLink {
 
 impl KernelInfoReply {
     /// See [Kernel info documentation](https://jupyter-client.readthedocs.io/en/stable/messaging.html#kernel-info)
-    pub fn build(info: LanguageInfo) -> KernelInfoReply {
+    ///
+    /// Fields unknown to the protocol version of the `client` are left out.
+    pub fn build(info: &LanguageInfo, client: &JupyterMessageHeader) -> KernelInfoReply {
+        let client = client.protocol_version();
         KernelInfoReply {
             status: "ok".to_owned(),
-            protocol_version: "5.3".to_owned(),
+            protocol_version: protocol_version(),
             implementation: env!("CARGO_PKG_NAME").to_owned(),
             implementation_version: env!("CARGO_PKG_VERSION").to_owned(),
             language_info: SealedLanguageInfo {
-                name: info.language,
-                version: info.version,
-                mimetype: info.mimetype,
-                file_extension: info.file_extensions,
-                pygment_lexer: info.lexer,
-                codemirror_mode: info.highlighter,
-                nbconvert_exporter: info.exporter,
+                name: info.language.clone(),
+                version: info.version.clone(),
+                mimetype: info.mimetype.clone(),
+                file_extension: info.file_extensions.clone(),
+                pygment_lexer: info.lexer.clone(),
+                codemirror_mode: info.highlighter.clone(),
+                nbconvert_exporter: info.exporter.clone(),
+            },
+            // the debugger is added in 5.2
+            debugger: info.debugger && client >= (5, 2),
+            banner: info.banner.clone(),
+            help_links: info.help_links.iter().map(|(text, url)| HelpLink { text: text.clone(), url: url.clone() }).collect(),
+            // supported features are added in 5.5
+            supported_features: match client >= (5, 5) {
+                true => Some(info.supported_features.clone()),
+                false => None,
             },
-            debugger: true,
-            banner: format!("Jupyter Server Protocol v{} in Rust", env!("CARGO_PKG_VERSION")),
-            help_links: vec![HelpLink {
-                text: "Rust std docs".to_owned(),
-                url: "https://doc.rust-lang.org/std/index.html".to_owned(),
-            }],
         }
     }
 }
@@ -11,16 +11,52 @@ pub enum JupyterMessageType {
     CommonInfoRequest,
     /// - [comm_info_reply](https://jupyter-client.readthedocs.io/en/stable/messaging.html#comm-info)
     CommonInfoReply,
+    /// - [comm_open](https://jupyter-client.readthedocs.io/en/stable/messaging.html#opening-a-comm)
+    CommOpen,
+    /// - [comm_msg](https://jupyter-client.readthedocs.io/en/stable/messaging.html#comm-messages)
+    CommMessage,
+    /// - [comm_close](https://jupyter-client.readthedocs.io/en/stable/messaging.html#tearing-down-comms)
+    CommClose,
     /// - [kernel_info_request](https://jupyter-client.readthedocs.io/en/stable/messaging.html#kernel-info)
     KernelInfoRequest,
     /// - [kernel_info_reply](https://jupyter-client.readthedocs.io/en/stable/messaging.html#kernel-info)
     KernelInfoReply,
     /// - [execute_request](https://jupyter-client.readthedocs.io/en/stable/messaging.html#code-inputs)
     ExecuteRequest,
+    /// - [execute_input](https://jupyter-client.readthedocs.io/en/stable/messaging.html#code-inputs)
+    ExecuteInput,
     /// - [execute_result](https://jupyter-client.readthedocs.io/en/stable/messaging.html#execution-results)
     ExecuteResult,
     /// - [execute_result](https://jupyter-client.readthedocs.io/en/stable/messaging.html#execution-results)
     ExecuteReply,
+    /// - [display_data](https://jupyter-client.readthedocs.io/en/stable/messaging.html#display-data)
+    DisplayData,
+    /// - [update_display_data](https://jupyter-client.readthedocs.io/en/stable/messaging.html#update-display-data)
+    UpdateDisplayData,
+    /// - [clear_output](https://jupyter-client.readthedocs.io/en/stable/messaging.html#clear-output)
+    ClearOutput,
+    /// - [error](https://jupyter-client.readthedocs.io/en/stable/messaging.html#execution-errors)
+    Error,
+    /// - [complete_request](https://jupyter-client.readthedocs.io/en/stable/messaging.html#completion)
+    CompleteRequest,
+    /// - [complete_reply](https://jupyter-client.readthedocs.io/en/stable/messaging.html#completion)
+    CompleteReply,
+    /// - [inspect_request](https://jupyter-client.readthedocs.io/en/stable/messaging.html#introspection)
+    InspectRequest,
+    /// - [inspect_reply](https://jupyter-client.readthedocs.io/en/stable/messaging.html#introspection)
+    InspectReply,
+    /// - [is_complete_request](https://jupyter-client.readthedocs.io/en/stable/messaging.html#code-completeness)
+    IsCompleteRequest,
+    /// - [is_complete_reply](https://jupyter-client.readthedocs.io/en/stable/messaging.html#code-completeness)
+    IsCompleteReply,
+    /// - [history_request](https://jupyter-client.readthedocs.io/en/stable/messaging.html#history)
+    HistoryRequest,
+    /// - [history_reply](https://jupyter-client.readthedocs.io/en/stable/messaging.html#history)
+    HistoryReply,
+    /// - [input_request](https://jupyter-client.readthedocs.io/en/stable/messaging.html#messages-on-the-stdin-router-dealer-channel)
+    InputRequest,
+    /// - [input_reply](https://jupyter-client.readthedocs.io/en/stable/messaging.html#messages-on-the-stdin-router-dealer-channel)
+    InputReply,
     /// - [debug_request](https://jupyter-client.readthedocs.io/en/stable/messaging.html#debug-request)
     DebugRequest,
     /// - [debug_reply](https://jupyter-client.readthedocs.io/en/stable/messaging.html#debug-request)
@@ -58,13 +94,31 @@ impl AsRef<str> for JupyterMessageType {
         match self {
             Self::StatusReply => "status",
             Self::Stream => "stream",
+            Self::CommOpen => "comm_open",
+            Self::CommMessage => "comm_msg",
+            Self::CommClose => "comm_close",
             Self::KernelInfoRequest => "kernel_info_request",
             Self::KernelInfoReply => "kernel_info_reply",
             Self::CommonInfoRequest => "comm_info_request",
             Self::CommonInfoReply => "comm_info_reply",
             Self::ExecuteRequest => "execute_request",
+            Self::ExecuteInput => "execute_input",
             Self::ExecuteResult => "execute_result",
             Self::ExecuteReply => "execute_reply",
+            Self::DisplayData => "display_data",
+            Self::UpdateDisplayData => "update_display_data",
+            Self::ClearOutput => "clear_output",
+            Self::Error => "error",
+            Self::CompleteRequest => "complete_request",
+            Self::CompleteReply => "complete_reply",
+            Self::InspectRequest => "inspect_request",
+            Self::InspectReply => "inspect_reply",
+            Self::IsCompleteRequest => "is_complete_request",
+            Self::IsCompleteReply => "is_complete_reply",
+            Self::HistoryRequest => "history_request",
+            Self::HistoryReply => "history_reply",
+            Self::InputRequest => "input_request",
+            Self::InputReply => "input_reply",
             Self::DebugRequest => "debug_request",
             Self::DebugReply => "debug_reply",
             Self::DebugEvent => "debug_event",
@@ -89,7 +143,15 @@ impl JupyterMessageType {
             "status" => JupyterMessageType::StatusReply,
             "kernel_info" | "kernel_info_request" => JupyterMessageType::KernelInfoRequest,
             "comm_info_request" => JupyterMessageType::CommonInfoRequest,
+            "comm_open" => JupyterMessageType::CommOpen,
+            "comm_msg" => JupyterMessageType::CommMessage,
+            "comm_close" => JupyterMessageType::CommClose,
             "execute_request" => JupyterMessageType::ExecuteRequest,
+            "complete_request" => JupyterMessageType::CompleteRequest,
+            "inspect_request" => JupyterMessageType::InspectRequest,
+            "is_complete_request" => JupyterMessageType::IsCompleteRequest,
+            "history_request" => JupyterMessageType::HistoryRequest,
+            "input_reply" => JupyterMessageType::InputReply,
             "debug_request" => JupyterMessageType::DebugRequest,
             "interrupt_request" => JupyterMessageType::InterruptRequest,
             "shutdown_request" => JupyterMessageType::ShutdownRequest,
@@ -108,6 +170,10 @@ impl JupyterMessageType {
             JupyterMessageType::KernelInfoRequest => JupyterMessageType::KernelInfoReply,
             JupyterMessageType::CommonInfoRequest => JupyterMessageType::CommonInfoReply,
             JupyterMessageType::ExecuteRequest => JupyterMessageType::ExecuteReply,
+            JupyterMessageType::CompleteRequest => JupyterMessageType::CompleteReply,
+            JupyterMessageType::InspectRequest => JupyterMessageType::InspectReply,
+            JupyterMessageType::IsCompleteRequest => JupyterMessageType::IsCompleteReply,
+            JupyterMessageType::HistoryRequest => JupyterMessageType::HistoryReply,
             JupyterMessageType::InterruptRequest => JupyterMessageType::InterruptReply,
             JupyterMessageType::ShutdownRequest => JupyterMessageType::ShutdownReply,
             JupyterMessageType::DebugRequest => JupyterMessageType::DebugReply,
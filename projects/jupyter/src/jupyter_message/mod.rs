@@ -1,28 +1,42 @@
+mod comm;
 mod common_info;
+mod complete;
 mod debug_info;
 mod der;
+mod display;
 mod execute;
+mod history;
+mod input;
+mod inspect;
 mod interrupt;
+mod is_complete;
 mod kernel_info;
 mod message_type;
 mod ser;
 mod shutdown;
 
 pub use self::{
+    comm::{CommMessage, CommOpen},
     common_info::CommonInfoRequest,
-    execute::{ExecutionRequest, ExecutionResult},
+    complete::{CompletionItem, CompletionReply, CompletionRequest},
+    display::{ClearOutput, DisplayData},
+    execute::{ExecutionInput, ExecutionRequest, ExecutionResult},
+    history::{HistoryAccess, HistoryEntry, HistoryRequest},
+    input::{InputReply, InputRequest},
+    inspect::InspectionRequest,
+    interrupt::InterruptReply,
+    is_complete::{IsCompleteReply, IsCompleteRequest},
     kernel_info::KernelInfoReply,
     message_type::JupyterMessageType,
+    shutdown::ShutdownRequest,
 };
 use crate::{
-    connection::{Connection, HmacSha256},
-    errors::JupyterError,
+    connection::{Connection, JupyterMac, ReplayCache, SignatureScheme},
+    errors::{JupyterError, JupyterErrorKind},
     ExecutionReply, JupyterResult,
 };
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use generic_array::GenericArray;
-use hmac::Mac;
 use serde::{
     de::DeserializeOwned,
     ser::{SerializeMap, SerializeStruct},
@@ -48,72 +62,79 @@ struct RawMessage {
 
 impl RawMessage {
     pub(crate) async fn read<S: SocketRecv>(connection: &mut Connection<S>) -> JupyterResult<RawMessage> {
-        Self::from_multipart(connection.socket.recv().await?, connection)
+        let parts = connection.socket.recv().await?.into_vec();
+        Self::from_multipart(parts, connection.mac.as_ref(), connection.replay.as_mut())
     }
 
-    pub(crate) fn from_multipart<S>(multipart: zeromq::ZmqMessage, connection: &Connection<S>) -> JupyterResult<RawMessage> {
-        let delimiter_index =
-            multipart.iter().position(|part| &part[..] == DELIMITER).ok_or_else(|| panic!("Missing delimeter"))?;
-        let mut parts = multipart.into_vec();
+    pub(crate) fn from_multipart(
+        mut parts: Vec<Bytes>,
+        mac: Option<&JupyterMac>,
+        replay: Option<&mut ReplayCache>,
+    ) -> JupyterResult<RawMessage> {
+        let delimiter_index = match parts.iter().position(|part| &part[..] == DELIMITER) {
+            Some(o) => o,
+            None => return Err(JupyterErrorKind::MissingDelimiter.into()),
+        };
+        if parts.len() < delimiter_index + 2 {
+            return Err(JupyterErrorKind::MalformedPart("signature", "missing after delimiter".to_string()).into());
+        }
         let jparts: Vec<_> = parts.drain(delimiter_index + 2..).collect();
-        let hmac = parts.pop().unwrap();
-        // Remove delimiter, so that what's left is just the identities.
-        parts.pop();
+        // Remove signature and delimiter, so that what's left is just the identities.
+        let hmac = parts.split_off(delimiter_index).swap_remove(1);
         let zmq_identities = parts;
 
         let raw_message = RawMessage { zmq_identities, jparts };
 
-        if let Some(mac_template) = &connection.mac {
-            let mut mac = mac_template.clone();
-            raw_message.digest(&mut mac);
-
-            if let Err(error) = mac.verify(GenericArray::from_slice(&hex::decode(&hmac)?)) {
-                panic!("{}", error);
+        if let Some(mac) = mac {
+            let signature = hex::decode(&hmac).map_err(|_| JupyterErrorKind::BadSignature)?;
+            if !mac.verify(&raw_message.jparts, &signature) {
+                return Err(JupyterErrorKind::BadSignature.into());
+            }
+            // only verified signatures are cached, so that forged messages can not evict them
+            if let Some(cache) = replay {
+                if !cache.insert(&signature) {
+                    return Err(JupyterErrorKind::ReplayedMessage.into());
+                }
             }
         }
 
         Ok(raw_message)
     }
 
-    async fn send<S: SocketSend>(self, connection: &mut Connection<S>) -> JupyterResult<()> {
-        let hmac = if let Some(mac_template) = &connection.mac {
-            let mut mac = mac_template.clone();
-            self.digest(&mut mac);
-            hex::encode(mac.finalize().into_bytes().as_slice())
-        }
-        else {
-            String::new()
+    fn into_multipart(self, mac: Option<&JupyterMac>) -> Vec<Bytes> {
+        let hmac = match mac {
+            Some(mac) => mac.sign(&self.jparts),
+            None => String::new(),
         };
-        let mut parts: Vec<bytes::Bytes> = Vec::new();
-        for part in &self.zmq_identities {
-            parts.push(part.to_vec().into());
-        }
+        let mut parts = self.zmq_identities;
         parts.push(DELIMITER.into());
-        parts.push(hmac.as_bytes().to_vec().into());
-        for part in &self.jparts {
-            parts.push(part.to_vec().into());
-        }
+        parts.push(hmac.into());
+        parts.extend(self.jparts);
+        parts
+    }
+
+    async fn send<S: SocketSend>(self, connection: &mut Connection<S>) -> JupyterResult<()> {
+        let parts = self.into_multipart(connection.mac.as_ref());
         // ZmqMessage::try_from only fails if parts is empty, which it never
         // will be here.
-        let message = zeromq::ZmqMessage::try_from(parts).unwrap();
+        let message = zeromq::ZmqMessage::try_from(parts).map_err(JupyterError::custom)?;
         connection.socket.send(message).await?;
         Ok(())
     }
-
-    fn digest(&self, mac: &mut HmacSha256) {
-        for part in &self.jparts {
-            mac.update(part);
-        }
-    }
 }
 /// Represent a message from jupyter client
 #[derive(Clone, Debug)]
 pub struct JupyterMessage {
     zmq_identities: Vec<Bytes>,
     pub(crate) header: JupyterMessageHeader,
-    parent_header: JupyterMessageHeader,
+    pub(crate) parent_header: JupyterMessageHeader,
+    /// The header frame as received, sent back verbatim as the parent header of replies
+    header_frame: Option<Bytes>,
+    /// The parent header frame as received, or the header frame of the request
+    parent_header_frame: Option<Bytes>,
     metadata: Value,
     content: Value,
+    buffers: Vec<Bytes>,
 }
 
 impl Default for JupyterMessage {
@@ -122,8 +143,11 @@ impl Default for JupyterMessage {
             zmq_identities: Vec::new(),
             header: JupyterMessageHeader::default(),
             parent_header: JupyterMessageHeader::default(),
+            header_frame: None,
+            parent_header_frame: None,
             metadata: Value::default(),
             content: Value::default(),
+            buffers: Vec::new(),
         }
     }
 }
@@ -139,14 +163,23 @@ impl ExecutionState {
     }
 }
 
+/// The header of a message, see [message header](https://jupyter-client.readthedocs.io/en/stable/messaging.html#message-header)
 #[derive(Clone, Debug)]
 pub struct JupyterMessageHeader {
+    /// The user who sent the message
     pub username: String,
+    /// The type of the message
     pub msg_type: JupyterMessageType,
+    /// The time when the message was created
     pub date: DateTime<Utc>,
-    pub session: Uuid,
-    pub msg_id: Uuid,
+    /// The id of the session, an opaque string
+    pub session: String,
+    /// The id of the message, an opaque string
+    pub msg_id: String,
+    /// The version of the message protocol
     pub version: String,
+    /// The fields not defined by this crate, e.g. `subshell_id`, kept so that they survive a round trip
+    pub extra: Map<String, Value>,
 }
 
 impl Default for JupyterMessageHeader {
@@ -158,6 +191,19 @@ impl Default for JupyterMessageHeader {
             session: Default::default(),
             username: "".to_string(),
             version: "".to_string(),
+            extra: Map::new(),
+        }
+    }
+}
+
+impl JupyterMessageHeader {
+    /// The protocol version of the sender as `(major, minor)`, `(5, 0)` if it is missing or malformed
+    pub(crate) fn protocol_version(&self) -> (u32, u32) {
+        let mut parts = self.version.split('.').map(str::parse::<u32>);
+        match (parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor))) => (major, minor),
+            (Some(Ok(major)), None) => (major, 0),
+            _ => (5, 0),
         }
     }
 }
@@ -167,12 +213,28 @@ const DELIMITER: &[u8] = b"<IDS|MSG>";
 #[allow(unused)]
 impl JupyterMessage {
     pub(crate) async fn read<S: SocketRecv>(connection: &mut Connection<S>) -> JupyterResult<JupyterMessage> {
-        Self::from_raw_message(RawMessage::read(connection).await?)
+        let message = RawMessage::read(connection).await.and_then(Self::from_raw_message);
+        if let Err(e) = &message {
+            connection.count_rejected(e);
+        }
+        message
     }
     /// Get the message type.
     pub fn kind(&self) -> &JupyterMessageType {
         &self.header.msg_type
     }
+    /// Get the header of this message.
+    pub fn header(&self) -> &JupyterMessageHeader {
+        &self.header
+    }
+    /// Get the header of the request this message belongs to, all fields are empty if there is none.
+    pub fn parent_header(&self) -> &JupyterMessageHeader {
+        &self.parent_header
+    }
+    /// Get the metadata, such as `cellId` of jupyterlab.
+    pub fn metadata(&self) -> &Value {
+        &self.metadata
+    }
     /// Change weakly typed content into strongly typed content.
     pub fn recast<T: DeserializeOwned>(&self) -> JupyterResult<T> {
         match from_value(self.content.clone()) {
@@ -180,40 +242,63 @@ impl JupyterMessage {
             Err(e) => Err(JupyterError::custom(format!("Expected {} but got {}", std::any::type_name::<T>(), e))),
         }
     }
+    /// Decode the frames of a message received from a socket, and verify the signature with `key`.
+    ///
+    /// Malformed or forged messages are reported as errors, such as [`JupyterErrorKind::BadSignature`].
+    pub fn decode(frames: Vec<Bytes>, scheme: SignatureScheme, key: &str) -> JupyterResult<JupyterMessage> {
+        Self::from_raw_message(RawMessage::from_multipart(frames, JupyterMac::new(scheme, key)?.as_ref(), None)?)
+    }
+    /// Encode the message into frames to send by a socket, signed with `key`.
+    pub fn encode(&self, scheme: SignatureScheme, key: &str) -> JupyterResult<Vec<Bytes>> {
+        Ok(self.as_raw_message()?.into_multipart(JupyterMac::new(scheme, key)?.as_ref()))
+    }
     fn from_raw_message(raw_message: RawMessage) -> JupyterResult<JupyterMessage> {
-        fn message_to_json(message: &[u8]) -> JupyterResult<Value> {
-            let out = Value::from_str(std::str::from_utf8(message).unwrap_or("")).unwrap();
-            Ok(out)
+        fn part<T: DeserializeOwned>(name: &'static str, bytes: &[u8]) -> JupyterResult<T> {
+            from_slice(bytes).map_err(|e| JupyterErrorKind::MalformedPart(name, e.to_string()).into())
         }
 
         if raw_message.jparts.len() < 4 {
-            panic!("Insufficient message parts {}", raw_message.jparts.len());
+            return Err(JupyterErrorKind::MissingParts(raw_message.jparts.len()).into());
         }
 
         Ok(JupyterMessage {
             zmq_identities: raw_message.zmq_identities,
-            header: from_slice(&raw_message.jparts[0])?,
-            parent_header: from_slice(&raw_message.jparts[1])?,
-            metadata: message_to_json(&raw_message.jparts[2])?,
-            content: from_slice::<Value>(&raw_message.jparts[3])?,
+            header: part("header", &raw_message.jparts[0])?,
+            parent_header: part("parent_header", &raw_message.jparts[1])?,
+            header_frame: Some(raw_message.jparts[0].clone()),
+            parent_header_frame: Some(raw_message.jparts[1].clone()),
+            metadata: part("metadata", &raw_message.jparts[2])?,
+            content: part("content", &raw_message.jparts[3])?,
+            buffers: raw_message.jparts[4..].to_vec(),
         })
     }
 
     /// Creates a new child message of this message. ZMQ identities are not transferred.
     pub fn create_message(&self, kind: JupyterMessageType) -> JupyterMessage {
+        let mut extra = Map::new();
+        // replies must go to the subshell of the request
+        if let Some(subshell) = self.header.extra.get("subshell_id") {
+            extra.insert("subshell_id".to_string(), subshell.clone());
+        }
         JupyterMessage {
             zmq_identities: Vec::new(),
             header: JupyterMessageHeader {
                 username: "kernel".to_string(),
                 session: self.header.session.clone(),
-                version: self.header.version.clone(),
-                msg_id: Uuid::new_v4(),
+                // messages of the kernel carry its own protocol version, as ipykernel does
+                version: kernel_info::protocol_version(),
+                msg_id: Uuid::new_v4().to_string(),
                 msg_type: kind,
                 date: Utc::now(),
+                extra,
             },
             parent_header: self.header.clone(),
-            metadata: Value::Null,
+            // the frontend may compare the parent header with its request, so the date and unknown fields are kept
+            parent_header_frame: self.header_frame.clone(),
+            header_frame: None,
+            metadata: Value::Object(Map::new()),
             content: Value::Null,
+            buffers: Vec::new(),
         }
     }
 
@@ -235,27 +320,65 @@ impl JupyterMessage {
         self.content = to_value(content)?;
         Ok(self)
     }
+    /// Set the message metadata.
+    pub fn with_metadata<T: Serialize>(mut self, metadata: T) -> JupyterResult<JupyterMessage> {
+        self.metadata = to_value(metadata)?;
+        Ok(self)
+    }
+    /// Get the binary buffers after the content.
+    pub fn buffers(&self) -> &[Bytes] {
+        &self.buffers
+    }
+    /// Set the binary buffers, which are signed and sent after the content.
+    pub fn with_buffers<I>(mut self, buffers: I) -> JupyterMessage
+    where
+        I: IntoIterator,
+        I::Item: Into<Bytes>,
+    {
+        self.buffers = buffers.into_iter().map(Into::into).collect();
+        self
+    }
+    /// Set a header field which is not defined by this crate.
+    pub fn with_header_field<T: Serialize>(mut self, key: &str, value: T) -> JupyterResult<JupyterMessage> {
+        self.header.extra.insert(key.to_string(), to_value(value)?);
+        self.header_frame = None;
+        Ok(self)
+    }
     /// Set the message type to "reply".
     pub fn with_message_type(mut self, msg_type: JupyterMessageType) -> JupyterMessage {
         self.header.msg_type = msg_type;
+        self.header_frame = None;
         self
     }
     /// Set the message type to "reply".
     pub fn drop_parent_header(&mut self) {
         self.parent_header = JupyterMessageHeader::default();
+        self.parent_header_frame = None;
     }
 
     pub(crate) async fn send_by<S: SocketSend>(&self, connection: &mut Connection<S>) -> JupyterResult<()> {
+        self.as_raw_message()?.send(connection).await
+    }
+    fn as_raw_message(&self) -> JupyterResult<RawMessage> {
         // If performance is a concern, we can probably avoid the clone and to_vec calls with a bit of refactoring.
-        let raw = RawMessage {
-            zmq_identities: self.zmq_identities.clone(),
-            jparts: vec![
-                to_vec(&self.header)?.into(),
-                to_vec(&self.parent_header)?.into(),
-                to_vec(&self.metadata)?.into(),
-                to_vec(&self.content)?.into(),
-            ],
+        let frame = |raw: &Option<Bytes>, header: &JupyterMessageHeader| -> JupyterResult<Bytes> {
+            match raw {
+                Some(raw) => Ok(raw.clone()),
+                None => Ok(to_vec(header)?.into()),
+            }
         };
-        raw.send(connection).await
+        let mut jparts: Vec<Bytes> = vec![
+            frame(&self.header_frame, &self.header)?,
+            frame(&self.parent_header_frame, &self.parent_header)?,
+            // the spec requires an empty dict instead of `null`
+            match &self.metadata {
+                Value::Null => Bytes::from_static(b"{}"),
+                metadata => to_vec(metadata)?.into(),
+            },
+            to_vec(&self.content)?.into(),
+        ];
+        // cloning `Bytes` only increases the reference count
+        jparts.extend(self.buffers.iter().cloned());
+        Ok(RawMessage { zmq_identities: self.zmq_identities.clone(), jparts })
     }
 }
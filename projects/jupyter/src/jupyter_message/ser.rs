@@ -1,22 +1,27 @@
 use super::*;
-use serde::{ser::SerializeStruct, Serializer};
+use serde::{ser::SerializeMap, Serializer};
 
 impl Serialize for JupyterMessageHeader {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("JupyterMessageHeader", 6)?;
+        // the parent header of a message without parent must be an empty dict
         if self.msg_type.is_empty() {
-            state.end()
+            serializer.serialize_map(Some(0))?.end()
         }
         else {
-            state.serialize_field("date", &self.date.to_rfc3339())?;
-            state.serialize_field("msg_id", &self.msg_id)?;
-            state.serialize_field("msg_type", &self.msg_type)?;
-            state.serialize_field("session", &self.session)?;
-            state.serialize_field("username", &self.username)?;
-            state.serialize_field("version", &self.version)?;
+            let mut state = serializer.serialize_map(Some(6 + self.extra.len()))?;
+            state.serialize_entry("date", &self.date.to_rfc3339())?;
+            state.serialize_entry("msg_id", &self.msg_id)?;
+            state.serialize_entry("msg_type", &self.msg_type)?;
+            state.serialize_entry("session", &self.session)?;
+            state.serialize_entry("username", &self.username)?;
+            state.serialize_entry("version", &self.version)?;
+            // unknown fields are sent back as they are
+            for (key, value) in &self.extra {
+                state.serialize_entry(key, value)?;
+            }
             state.end()
         }
     }
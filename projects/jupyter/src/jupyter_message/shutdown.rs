@@ -1,30 +1,33 @@
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
+/// The request to shutdown or restart the kernel
 #[derive(Clone, Debug, Deserialize)]
 pub struct ShutdownRequest {
-    restart: bool,
+    /// Whether the kernel will be restarted after shutdown
+    #[serde(default)]
+    pub restart: bool,
 }
 
+/// The reply of [`ShutdownRequest`], sent before the kernel exits
 #[derive(Clone, Debug)]
 pub struct ShutdownReply {
     /// return true if restart, or false if finally shutdown
     pub restart: bool,
 }
 
-#[allow(dead_code)]
 impl ShutdownRequest {
-    /// will not send in face, will be killed with no response
+    /// Confirm the shutdown with the same `restart` flag
     pub fn as_reply(&self) -> ShutdownReply {
-        ShutdownReply { restart: true }
+        ShutdownReply { restart: self.restart }
     }
 }
 
-impl Serialize for ShutdownRequest {
+impl Serialize for ShutdownReply {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("ShutdownRequest", 2)?;
+        let mut s = serializer.serialize_struct("ShutdownReply", 2)?;
         s.serialize_field("status", "ok")?;
         s.serialize_field("restart", &self.restart)?;
         s.end()
@@ -13,18 +13,23 @@ mod errors;
 mod executor;
 pub(crate) mod jupyter_message;
 pub mod value_type;
+pub mod widgets;
 
-pub use crate::jupyter_message::JupyterMessage;
+pub use crate::jupyter_message::{JupyterMessage, JupyterMessageHeader};
 #[allow(deprecated)]
 pub use crate::{
-    commands::{InstallAction, OpenAction, StartAction, UninstallAction},
+    commands::{InstallAction, InterruptMode, OpenAction, StartAction, UninstallAction},
+    connection::SignatureScheme,
     errors::{JupyterError, JupyterErrorKind, JupyterResult},
     executor::{
-        execution_reply::{ExecutionPayload, ExecutionReply},
+        comms::{JupyterComm, JupyterCommTarget, LINT_TARGET},
+        display::DisplayHandle,
+        execution_reply::{ExecutionError, ExecutionPayload, ExecutionReply},
+        interrupt::InterruptToken,
         sockets::{JupyterConnection, JupyterKernelSockets, JupyterStream},
         JupyterKernelProtocol, LanguageInfo,
     },
-    jupyter_message::{ExecutionRequest, ExecutionResult},
+    jupyter_message::{CompletionItem, CompletionReply, ExecutionRequest, ExecutionResult, IsCompleteReply},
 };
 pub use jupyter_types::{third_party, Executed};
 pub use serde::Serialize;
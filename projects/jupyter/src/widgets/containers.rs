@@ -0,0 +1,132 @@
+use super::*;
+
+/// Layout widgets horizontally, `HBoxModel` in ipywidgets.
+#[derive(Clone, Debug, Default)]
+pub struct HBox {
+    children: Vec<Value>,
+}
+
+/// Layout widgets vertically, `VBoxModel` in ipywidgets.
+#[derive(Clone, Debug, Default)]
+pub struct VBox {
+    children: Vec<Value>,
+}
+
+/// Capture and show outputs inside a widget, `OutputModel` in ipywidgets.
+#[derive(Clone, Debug, Default)]
+pub struct Output {
+    outputs: Vec<Value>,
+}
+
+impl HBox {
+    /// Create a new horizontal box with created widgets
+    pub fn new<'i, I>(children: I) -> Self
+    where
+        I: IntoIterator<Item = &'i WidgetHandle>,
+    {
+        Self { children: children.into_iter().map(|v| v.as_reference()).collect() }
+    }
+}
+
+impl VBox {
+    /// Create a new vertical box with created widgets
+    pub fn new<'i, I>(children: I) -> Self
+    where
+        I: IntoIterator<Item = &'i WidgetHandle>,
+    {
+        Self { children: children.into_iter().map(|v| v.as_reference()).collect() }
+    }
+}
+
+impl Output {
+    /// Create a new empty output area
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Append an output, use [`JupyterKernelSockets::update_widget`] with [`JupyterWidget::get_state`] to replace
+    /// the outputs of a created widget
+    pub fn with_output<T: Executed>(mut self, executed: T) -> Self {
+        let mut data = Map::new();
+        data.insert(executed.mime_type(), executed.as_json(&JupyterContext::default()));
+        let mut output = Map::new();
+        output.insert("output_type".to_string(), Value::from("display_data"));
+        output.insert("data".to_string(), Value::Object(data));
+        output.insert("metadata".to_string(), Value::Object(Map::new()));
+        self.outputs.push(Value::Object(output));
+        self
+    }
+}
+
+impl JupyterWidget for HBox {
+    fn model_name(&self) -> &'static str {
+        "HBoxModel"
+    }
+
+    fn view_name(&self) -> &'static str {
+        "HBoxView"
+    }
+
+    fn get_state(&self) -> Map<String, Value> {
+        box_state(&self.children)
+    }
+
+    fn set_state(&mut self, state: &Map<String, Value>) {
+        if let Some(v) = state.get("children").and_then(Value::as_array) {
+            self.children = v.clone()
+        }
+    }
+}
+
+impl JupyterWidget for VBox {
+    fn model_name(&self) -> &'static str {
+        "VBoxModel"
+    }
+
+    fn view_name(&self) -> &'static str {
+        "VBoxView"
+    }
+
+    fn get_state(&self) -> Map<String, Value> {
+        box_state(&self.children)
+    }
+
+    fn set_state(&mut self, state: &Map<String, Value>) {
+        if let Some(v) = state.get("children").and_then(Value::as_array) {
+            self.children = v.clone()
+        }
+    }
+}
+
+impl JupyterWidget for Output {
+    fn model_name(&self) -> &'static str {
+        "OutputModel"
+    }
+
+    fn view_name(&self) -> &'static str {
+        "OutputView"
+    }
+
+    fn module(&self) -> (&'static str, &'static str) {
+        OUTPUT_MODULE
+    }
+
+    fn get_state(&self) -> Map<String, Value> {
+        let mut state = Map::new();
+        state.insert("outputs".to_string(), Value::Array(self.outputs.clone()));
+        state.insert("msg_id".to_string(), Value::from(""));
+        state
+    }
+
+    fn set_state(&mut self, state: &Map<String, Value>) {
+        if let Some(v) = state.get("outputs").and_then(Value::as_array) {
+            self.outputs = v.clone()
+        }
+    }
+}
+
+fn box_state(children: &[Value]) -> Map<String, Value> {
+    let mut state = Map::new();
+    state.insert("children".to_string(), Value::Array(children.to_vec()));
+    state.insert("box_style".to_string(), Value::from(""));
+    state
+}
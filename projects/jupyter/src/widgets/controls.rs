@@ -0,0 +1,396 @@
+use super::*;
+
+/// A slider of integers, `IntSliderModel` in ipywidgets.
+#[derive(Debug, Default)]
+pub struct IntSlider {
+    value: i64,
+    min: i64,
+    max: i64,
+    step: i64,
+    description: String,
+    disabled: bool,
+    on_change: WidgetCallback<i64>,
+}
+
+/// A slider of floats, `FloatSliderModel` in ipywidgets.
+#[derive(Debug, Default)]
+pub struct FloatSlider {
+    value: f64,
+    min: f64,
+    max: f64,
+    step: f64,
+    description: String,
+    disabled: bool,
+    on_change: WidgetCallback<f64>,
+}
+
+/// A single line text input, `TextModel` in ipywidgets.
+#[derive(Debug, Default)]
+pub struct Text {
+    value: String,
+    placeholder: String,
+    description: String,
+    disabled: bool,
+    on_change: WidgetCallback<String>,
+}
+
+/// A clickable button, `ButtonModel` in ipywidgets.
+#[derive(Debug, Default)]
+pub struct Button {
+    description: String,
+    tooltip: String,
+    icon: String,
+    disabled: bool,
+    on_click: WidgetCallback<()>,
+}
+
+/// A dropdown list to select one of the options, `DropdownModel` in ipywidgets.
+#[derive(Debug, Default)]
+pub struct Dropdown {
+    options: Vec<String>,
+    index: Option<usize>,
+    description: String,
+    disabled: bool,
+    on_change: WidgetCallback<usize>,
+}
+
+impl IntSlider {
+    /// Create a new slider between `min` and `max`, with step 1
+    pub fn new(min: i64, max: i64) -> Self {
+        Self { value: min, min, max, step: 1, ..Default::default() }
+    }
+    /// Set the initial value
+    pub fn with_value(self, value: i64) -> Self {
+        Self { value, ..self }
+    }
+    /// Set the step of the slider
+    pub fn with_step(self, step: i64) -> Self {
+        Self { step, ..self }
+    }
+    /// Set the label before the slider
+    pub fn with_description<S: ToString>(self, description: S) -> Self {
+        Self { description: description.to_string(), ..self }
+    }
+    /// Called with the new value when the user drags the slider
+    pub fn on_change<F>(self, callback: F) -> Self
+    where
+        F: FnMut(i64) + Send + 'static,
+    {
+        Self { on_change: WidgetCallback::new(callback), ..self }
+    }
+}
+
+impl JupyterWidget for IntSlider {
+    fn model_name(&self) -> &'static str {
+        "IntSliderModel"
+    }
+
+    fn view_name(&self) -> &'static str {
+        "IntSliderView"
+    }
+
+    fn get_state(&self) -> Map<String, Value> {
+        let mut state = Map::new();
+        state.insert("value".to_string(), Value::from(self.value));
+        state.insert("min".to_string(), Value::from(self.min));
+        state.insert("max".to_string(), Value::from(self.max));
+        state.insert("step".to_string(), Value::from(self.step));
+        state.insert("description".to_string(), Value::from(self.description.as_str()));
+        state.insert("disabled".to_string(), Value::from(self.disabled));
+        state.insert("orientation".to_string(), Value::from("horizontal"));
+        state.insert("readout".to_string(), Value::from(true));
+        state.insert("readout_format".to_string(), Value::from("d"));
+        state.insert("continuous_update".to_string(), Value::from(true));
+        state
+    }
+
+    fn set_state(&mut self, state: &Map<String, Value>) {
+        if let Some(v) = state.get("min").and_then(Value::as_i64) {
+            self.min = v
+        }
+        if let Some(v) = state.get("max").and_then(Value::as_i64) {
+            self.max = v
+        }
+        if let Some(v) = state.get("step").and_then(Value::as_i64) {
+            self.step = v
+        }
+        if let Some(v) = state.get("description").and_then(Value::as_str) {
+            self.description = v.to_string()
+        }
+        if let Some(v) = state.get("disabled").and_then(Value::as_bool) {
+            self.disabled = v
+        }
+        if let Some(v) = state.get("value").and_then(Value::as_i64) {
+            if v != self.value {
+                self.value = v;
+                self.on_change.call(v);
+            }
+        }
+    }
+}
+
+impl FloatSlider {
+    /// Create a new slider between `min` and `max`, with step 0.1
+    pub fn new(min: f64, max: f64) -> Self {
+        Self { value: min, min, max, step: 0.1, ..Default::default() }
+    }
+    /// Set the initial value
+    pub fn with_value(self, value: f64) -> Self {
+        Self { value, ..self }
+    }
+    /// Set the step of the slider
+    pub fn with_step(self, step: f64) -> Self {
+        Self { step, ..self }
+    }
+    /// Set the label before the slider
+    pub fn with_description<S: ToString>(self, description: S) -> Self {
+        Self { description: description.to_string(), ..self }
+    }
+    /// Called with the new value when the user drags the slider
+    pub fn on_change<F>(self, callback: F) -> Self
+    where
+        F: FnMut(f64) + Send + 'static,
+    {
+        Self { on_change: WidgetCallback::new(callback), ..self }
+    }
+}
+
+impl JupyterWidget for FloatSlider {
+    fn model_name(&self) -> &'static str {
+        "FloatSliderModel"
+    }
+
+    fn view_name(&self) -> &'static str {
+        "FloatSliderView"
+    }
+
+    fn get_state(&self) -> Map<String, Value> {
+        let mut state = Map::new();
+        state.insert("value".to_string(), Value::from(self.value));
+        state.insert("min".to_string(), Value::from(self.min));
+        state.insert("max".to_string(), Value::from(self.max));
+        state.insert("step".to_string(), Value::from(self.step));
+        state.insert("description".to_string(), Value::from(self.description.as_str()));
+        state.insert("disabled".to_string(), Value::from(self.disabled));
+        state.insert("orientation".to_string(), Value::from("horizontal"));
+        state.insert("readout".to_string(), Value::from(true));
+        state.insert("readout_format".to_string(), Value::from(".2f"));
+        state.insert("continuous_update".to_string(), Value::from(true));
+        state
+    }
+
+    fn set_state(&mut self, state: &Map<String, Value>) {
+        if let Some(v) = state.get("min").and_then(Value::as_f64) {
+            self.min = v
+        }
+        if let Some(v) = state.get("max").and_then(Value::as_f64) {
+            self.max = v
+        }
+        if let Some(v) = state.get("step").and_then(Value::as_f64) {
+            self.step = v
+        }
+        if let Some(v) = state.get("description").and_then(Value::as_str) {
+            self.description = v.to_string()
+        }
+        if let Some(v) = state.get("disabled").and_then(Value::as_bool) {
+            self.disabled = v
+        }
+        if let Some(v) = state.get("value").and_then(Value::as_f64) {
+            if v != self.value {
+                self.value = v;
+                self.on_change.call(v);
+            }
+        }
+    }
+}
+
+impl Text {
+    /// Create a new text input with initial value
+    pub fn new<S: ToString>(value: S) -> Self {
+        Self { value: value.to_string(), ..Default::default() }
+    }
+    /// Set the hint text when the input is empty
+    pub fn with_placeholder<S: ToString>(self, placeholder: S) -> Self {
+        Self { placeholder: placeholder.to_string(), ..self }
+    }
+    /// Set the label before the input
+    pub fn with_description<S: ToString>(self, description: S) -> Self {
+        Self { description: description.to_string(), ..self }
+    }
+    /// Called with the new text when the user types
+    pub fn on_change<F>(self, callback: F) -> Self
+    where
+        F: FnMut(String) + Send + 'static,
+    {
+        Self { on_change: WidgetCallback::new(callback), ..self }
+    }
+}
+
+impl JupyterWidget for Text {
+    fn model_name(&self) -> &'static str {
+        "TextModel"
+    }
+
+    fn view_name(&self) -> &'static str {
+        "TextView"
+    }
+
+    fn get_state(&self) -> Map<String, Value> {
+        let mut state = Map::new();
+        state.insert("value".to_string(), Value::from(self.value.as_str()));
+        state.insert("placeholder".to_string(), Value::from(self.placeholder.as_str()));
+        state.insert("description".to_string(), Value::from(self.description.as_str()));
+        state.insert("disabled".to_string(), Value::from(self.disabled));
+        state.insert("continuous_update".to_string(), Value::from(true));
+        state
+    }
+
+    fn set_state(&mut self, state: &Map<String, Value>) {
+        if let Some(v) = state.get("placeholder").and_then(Value::as_str) {
+            self.placeholder = v.to_string()
+        }
+        if let Some(v) = state.get("description").and_then(Value::as_str) {
+            self.description = v.to_string()
+        }
+        if let Some(v) = state.get("disabled").and_then(Value::as_bool) {
+            self.disabled = v
+        }
+        if let Some(v) = state.get("value").and_then(Value::as_str) {
+            if v != self.value {
+                self.value = v.to_string();
+                self.on_change.call(self.value.clone());
+            }
+        }
+    }
+}
+
+impl Button {
+    /// Create a new button with the text on it
+    pub fn new<S: ToString>(description: S) -> Self {
+        Self { description: description.to_string(), ..Default::default() }
+    }
+    /// Set the text shown when hovering
+    pub fn with_tooltip<S: ToString>(self, tooltip: S) -> Self {
+        Self { tooltip: tooltip.to_string(), ..self }
+    }
+    /// Set the [font-awesome](https://fontawesome.com/icons) icon name, without the `fa-` prefix
+    pub fn with_icon<S: ToString>(self, icon: S) -> Self {
+        Self { icon: icon.to_string(), ..self }
+    }
+    /// Called when the user clicks the button
+    pub fn on_click<F>(self, mut callback: F) -> Self
+    where
+        F: FnMut() + Send + 'static,
+    {
+        Self { on_click: WidgetCallback::new(move |_| callback()), ..self }
+    }
+}
+
+impl JupyterWidget for Button {
+    fn model_name(&self) -> &'static str {
+        "ButtonModel"
+    }
+
+    fn view_name(&self) -> &'static str {
+        "ButtonView"
+    }
+
+    fn get_state(&self) -> Map<String, Value> {
+        let mut state = Map::new();
+        state.insert("description".to_string(), Value::from(self.description.as_str()));
+        state.insert("tooltip".to_string(), Value::from(self.tooltip.as_str()));
+        state.insert("icon".to_string(), Value::from(self.icon.as_str()));
+        state.insert("button_style".to_string(), Value::from(""));
+        state.insert("disabled".to_string(), Value::from(self.disabled));
+        state
+    }
+
+    fn set_state(&mut self, state: &Map<String, Value>) {
+        if let Some(v) = state.get("description").and_then(Value::as_str) {
+            self.description = v.to_string()
+        }
+        if let Some(v) = state.get("tooltip").and_then(Value::as_str) {
+            self.tooltip = v.to_string()
+        }
+        if let Some(v) = state.get("icon").and_then(Value::as_str) {
+            self.icon = v.to_string()
+        }
+        if let Some(v) = state.get("disabled").and_then(Value::as_bool) {
+            self.disabled = v
+        }
+    }
+
+    fn on_custom(&mut self, content: &Value) {
+        if content.get("event").and_then(Value::as_str) == Some("click") {
+            self.on_click.call(())
+        }
+    }
+}
+
+impl Dropdown {
+    /// Create a new dropdown with the labels of options, the first one is selected
+    pub fn new<I, S>(options: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        let options: Vec<String> = options.into_iter().map(|v| v.to_string()).collect();
+        let index = if options.is_empty() { None } else { Some(0) };
+        Self { options, index, ..Default::default() }
+    }
+    /// Set the selected option, `None` means nothing is selected
+    pub fn with_index(self, index: Option<usize>) -> Self {
+        Self { index, ..self }
+    }
+    /// Set the label before the dropdown
+    pub fn with_description<S: ToString>(self, description: S) -> Self {
+        Self { description: description.to_string(), ..self }
+    }
+    /// Called with the index of the new option when the user selects
+    pub fn on_change<F>(self, callback: F) -> Self
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        Self { on_change: WidgetCallback::new(callback), ..self }
+    }
+}
+
+impl JupyterWidget for Dropdown {
+    fn model_name(&self) -> &'static str {
+        "DropdownModel"
+    }
+
+    fn view_name(&self) -> &'static str {
+        "DropdownView"
+    }
+
+    fn get_state(&self) -> Map<String, Value> {
+        let mut state = Map::new();
+        state.insert("_options_labels".to_string(), Value::from(self.options.clone()));
+        state.insert("index".to_string(), self.index.map(Value::from).unwrap_or(Value::Null));
+        state.insert("description".to_string(), Value::from(self.description.as_str()));
+        state.insert("disabled".to_string(), Value::from(self.disabled));
+        state
+    }
+
+    fn set_state(&mut self, state: &Map<String, Value>) {
+        if let Some(v) = state.get("_options_labels").and_then(Value::as_array) {
+            self.options = v.iter().filter_map(Value::as_str).map(|v| v.to_string()).collect()
+        }
+        if let Some(v) = state.get("description").and_then(Value::as_str) {
+            self.description = v.to_string()
+        }
+        if let Some(v) = state.get("disabled").and_then(Value::as_bool) {
+            self.disabled = v
+        }
+        if let Some(v) = state.get("index") {
+            let index = v.as_u64().map(|v| v as usize);
+            if index != self.index {
+                self.index = index;
+                if let Some(i) = index {
+                    self.on_change.call(i)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! [Jupyter Widgets](https://github.com/jupyter-widgets/ipywidgets/blob/main/packages/schema/messages.md) on top of comms.
+//!
+//! Create a widget in [`JupyterKernelProtocol::running`](crate::JupyterKernelProtocol::running), then display the
+//! returned [`WidgetHandle`] like any other [`Executed`] value.
+//!
+//! ```rust, ignore
+//! let slider = IntSlider::new(0, 100).with_value(50).on_change(|v| tracing::info!("slider: {}", v));
+//! let handle = self.sockets.create_widget(slider, &code.header).await?;
+//! self.sockets.send_executed(handle, &code.header).await;
+//! ```
+//!
+//! Callbacks such as `on_change` run on the shell channel once the widget is updated, they may read
+//! [`JupyterKernelSockets::widget_state`] of any widget.
+
+mod containers;
+mod controls;
+
+pub use self::{
+    containers::{HBox, Output, VBox},
+    controls::{Button, Dropdown, FloatSlider, IntSlider, Text},
+};
+use crate::{
+    executor::comms::{JupyterComm, JupyterCommTarget},
+    JupyterError, JupyterKernelSockets, JupyterMessage, JupyterResult,
+};
+use bytes::Bytes;
+use jupyter_types::{Executed, JupyterContext};
+use serde_json::{Map, Value};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::{Debug, Formatter},
+    sync::{Arc, Mutex, MutexGuard},
+};
+use uuid::Uuid;
+
+/// The comm target name of widget models
+pub const WIDGET_TARGET: &str = "jupyter.widget";
+/// The version of widget message protocol
+pub const WIDGET_PROTOCOL_VERSION: &str = "2.1.0";
+/// The module of `@jupyter-widgets/controls` models
+pub const CONTROLS_MODULE: (&str, &str) = ("@jupyter-widgets/controls", "2.0.0");
+/// The module of `@jupyter-widgets/output` models
+pub const OUTPUT_MODULE: (&str, &str) = ("@jupyter-widgets/output", "1.0.0");
+
+/// A widget model which synchronizes its state with the frontend.
+#[allow(unused_variables)]
+pub trait JupyterWidget: Send + 'static {
+    /// The model name, e.g. `IntSliderModel`
+    fn model_name(&self) -> &'static str;
+    /// The view name, e.g. `IntSliderView`
+    fn view_name(&self) -> &'static str;
+    /// The module name and version of both model and view
+    fn module(&self) -> (&'static str, &'static str) {
+        CONTROLS_MODULE
+    }
+    /// The synchronized attributes, without `_model_*` and `_view_*` keys
+    fn get_state(&self) -> Map<String, Value>;
+    /// Apply the attributes changed by the frontend, unknown attributes should be ignored
+    fn set_state(&mut self, state: &Map<String, Value>);
+    /// The binary attributes, each buffer is sent along with its path in the state, e.g. `["value"]`
+    fn get_buffers(&self) -> Vec<WidgetBuffer> {
+        Vec::new()
+    }
+    /// Apply the binary attributes changed by the frontend
+    fn set_buffers(&mut self, buffers: Vec<WidgetBuffer>) {}
+    /// Handle a custom message from the frontend, e.g. click events of [`Button`]
+    fn on_custom(&mut self, content: &Value) {}
+}
+
+/// A binary attribute of a widget state, the path is a list of keys and indices in the state.
+pub type WidgetBuffer = (Vec<Value>, Bytes);
+
+/// A created widget, display it to render the widget views.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WidgetHandle {
+    comm: JupyterComm,
+}
+
+/// The callback when a widget value changes.
+///
+/// Callbacks are deferred until the widget registries are unlocked, so that they can read widget states.
+pub(crate) struct WidgetCallback<T> {
+    callback: Option<SharedCallback<T>>,
+}
+
+type SharedCallback<T> = Arc<Mutex<Box<dyn FnMut(T) + Send>>>;
+
+thread_local! {
+    /// The callbacks triggered while the registries are locked
+    static DEFERRED: RefCell<Vec<Box<dyn FnOnce()>>> = RefCell::new(Vec::new());
+}
+
+/// All created widgets, keyed by model id.
+pub(crate) type WidgetRegistry = Arc<Mutex<BTreeMap<String, Box<dyn JupyterWidget>>>>;
+
+/// The `jupyter.widget` comm target which dispatches messages to widget models.
+struct WidgetTarget {
+    widgets: WidgetRegistry,
+}
+
+impl<T> Default for WidgetCallback<T> {
+    fn default() -> Self {
+        Self { callback: None }
+    }
+}
+
+impl<T> Debug for WidgetCallback<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.callback.is_some() { "Some(Fn)" } else { "None" })
+    }
+}
+
+impl<T> WidgetCallback<T> {
+    pub(crate) fn new<F>(callback: F) -> Self
+    where
+        F: FnMut(T) + Send + 'static,
+    {
+        Self { callback: Some(Arc::new(Mutex::new(Box::new(callback)))) }
+    }
+    /// Call later in [`run_deferred_callbacks`]
+    pub(crate) fn call(&mut self, value: T)
+    where
+        T: 'static,
+    {
+        if let Some(callback) = &self.callback {
+            let callback = callback.clone();
+            let call = move || (callback.lock().unwrap_or_else(|e| e.into_inner()))(value);
+            DEFERRED.with(|v| v.borrow_mut().push(Box::new(call)));
+        }
+    }
+}
+
+/// Run the callbacks triggered on this thread, must be called after all registries are unlocked
+pub(crate) fn run_deferred_callbacks() {
+    let calls = DEFERRED.with(|v| std::mem::take(&mut *v.borrow_mut()));
+    for call in calls {
+        call()
+    }
+}
+
+impl WidgetHandle {
+    /// The model id of the widget, which is also the comm id
+    pub fn model_id(&self) -> &str {
+        self.comm.id()
+    }
+    /// The reference of the widget in other widget states, e.g. children of [`HBox`]
+    pub fn as_reference(&self) -> Value {
+        Value::String(format!("IPY_MODEL_{}", self.model_id()))
+    }
+}
+
+impl Executed for WidgetHandle {
+    fn mime_type(&self) -> String {
+        "application/vnd.jupyter.widget-view+json".to_string()
+    }
+
+    fn as_json(&self, _: &JupyterContext) -> Value {
+        let mut view = Map::new();
+        view.insert("model_id".to_string(), Value::from(self.model_id()));
+        view.insert("version_major".to_string(), Value::from(2));
+        view.insert("version_minor".to_string(), Value::from(0));
+        Value::Object(view)
+    }
+}
+
+impl JupyterCommTarget for WidgetTarget {
+    fn comm_message_with_buffers(&mut self, comm: &JupyterComm, data: Value, buffers: &[Bytes]) -> Option<(Value, Vec<Bytes>)> {
+        let mut widgets = lock_widgets(&self.widgets);
+        let widget = match widgets.get_mut(comm.id()) {
+            Some(o) => o,
+            None => {
+                tracing::warn!("No widget with model id {}", comm.id());
+                return None;
+            }
+        };
+        match data.get("method").and_then(Value::as_str) {
+            Some("update") => {
+                let state = data.get("state").and_then(Value::as_object).cloned().unwrap_or_default();
+                let buffers = received_buffers(&data, buffers);
+                widget.set_state(&state);
+                if !buffers.is_empty() {
+                    widget.set_buffers(buffers.clone());
+                }
+                // echo back so that other views of the same model keep in sync
+                Some(update_message("echo_update", state, buffers))
+            }
+            Some("request_state") => Some(update_message("update", full_state(widget.as_ref()), widget.get_buffers())),
+            Some("custom") => {
+                widget.on_custom(data.get("content").unwrap_or(&Value::Null));
+                None
+            }
+            method => {
+                tracing::warn!("Unknown widget method: {:?}", method);
+                None
+            }
+        }
+    }
+
+    fn comm_close(&mut self, comm: &JupyterComm, _: Value) {
+        lock_widgets(&self.widgets).remove(comm.id());
+    }
+}
+
+impl JupyterKernelSockets {
+    /// Create a widget model in the frontend, display the returned handle to show it.
+    pub async fn create_widget<W>(&self, widget: W, parent: &JupyterMessage) -> JupyterResult<WidgetHandle>
+    where
+        W: JupyterWidget,
+    {
+        let comm = JupyterComm { id: Uuid::new_v4().simple().to_string(), target_name: WIDGET_TARGET.to_string() };
+        let (data, buffers) = state_message(full_state(&widget), widget.get_buffers());
+        lock_widgets(&self.widgets).insert(comm.id.clone(), Box::new(widget));
+        let mut metadata = Map::new();
+        metadata.insert("version".to_string(), Value::from(WIDGET_PROTOCOL_VERSION));
+        self.open_comm_with(&comm, data, Value::Object(metadata), buffers, parent).await?;
+        Ok(WidgetHandle { comm })
+    }
+    /// Get the current state of a widget
+    pub fn widget_state(&self, widget: &WidgetHandle) -> Option<Map<String, Value>> {
+        lock_widgets(&self.widgets).get(widget.model_id()).map(|v| v.get_state())
+    }
+    /// Change attributes of a widget from the kernel side, and synchronize them to the frontend
+    pub async fn update_widget(
+        &self,
+        widget: &WidgetHandle,
+        state: Map<String, Value>,
+        parent: &JupyterMessage,
+    ) -> JupyterResult<()> {
+        match lock_widgets(&self.widgets).get_mut(widget.model_id()) {
+            Some(o) => o.set_state(&state),
+            None => return Err(JupyterError::custom(format!("No widget with model id {}", widget.model_id()))),
+        }
+        run_deferred_callbacks();
+        let (data, buffers) = update_message("update", state, Vec::new());
+        self.send_comm_with_buffers(&widget.comm, data, buffers, parent).await
+    }
+    /// Close a widget, all its views will be removed
+    pub async fn close_widget(&self, widget: &WidgetHandle, parent: &JupyterMessage) -> JupyterResult<()> {
+        lock_widgets(&self.widgets).remove(widget.model_id());
+        self.close_comm(&widget.comm, Value::Null, parent).await
+    }
+    /// Register the `jupyter.widget` target, called once when the kernel starts
+    pub(crate) fn register_widget_target(&self) {
+        self.register_comm_target(WIDGET_TARGET, WidgetTarget { widgets: self.widgets.clone() });
+    }
+}
+
+fn lock_widgets(widgets: &WidgetRegistry) -> MutexGuard<'_, BTreeMap<String, Box<dyn JupyterWidget>>> {
+    widgets.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn full_state(widget: &dyn JupyterWidget) -> Map<String, Value> {
+    let (module, version) = widget.module();
+    let mut state = Map::new();
+    state.insert("_model_name".to_string(), Value::from(widget.model_name()));
+    state.insert("_model_module".to_string(), Value::from(module));
+    state.insert("_model_module_version".to_string(), Value::from(version));
+    state.insert("_view_name".to_string(), Value::from(widget.view_name()));
+    state.insert("_view_module".to_string(), Value::from(module));
+    state.insert("_view_module_version".to_string(), Value::from(version));
+    state.extend(widget.get_state());
+    state
+}
+
+fn state_message(state: Map<String, Value>, buffers: Vec<WidgetBuffer>) -> (Value, Vec<Bytes>) {
+    let (paths, buffers): (Vec<_>, Vec<_>) = buffers.into_iter().map(|(path, bytes)| (Value::Array(path), bytes)).unzip();
+    let mut data = Map::new();
+    data.insert("state".to_string(), Value::Object(state));
+    data.insert("buffer_paths".to_string(), Value::Array(paths));
+    (Value::Object(data), buffers)
+}
+
+fn update_message(method: &str, state: Map<String, Value>, buffers: Vec<WidgetBuffer>) -> (Value, Vec<Bytes>) {
+    let (mut data, buffers) = state_message(state, buffers);
+    if let Value::Object(o) = &mut data {
+        o.insert("method".to_string(), Value::from(method));
+    }
+    (data, buffers)
+}
+
+/// Pair the `buffer_paths` of the data with the buffers of the message
+fn received_buffers(data: &Value, buffers: &[Bytes]) -> Vec<WidgetBuffer> {
+    let paths = data.get("buffer_paths").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    paths.iter().zip(buffers).filter_map(|(path, bytes)| Some((path.as_array()?.clone(), bytes.clone()))).collect()
+}
@@ -0,0 +1,19 @@
+use bytes::Bytes;
+use std::{fs, path::Path};
+
+/// Load the unsigned frames of each file in `tests/{dir}`, named by the path of the file.
+///
+/// Each file is a json array of the header, parent header, metadata and content parts.
+pub fn load_frames(dir: &str) -> Vec<(String, Vec<Bytes>)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(dir);
+    let mut messages = vec![];
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let parts: Vec<String> = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let mut frames = vec![Bytes::from("client-identity"), Bytes::from("<IDS|MSG>"), Bytes::new()];
+        frames.extend(parts.into_iter().map(Bytes::from));
+        messages.push((path.display().to_string(), frames));
+    }
+    assert!(!messages.is_empty());
+    messages
+}
@@ -0,0 +1,178 @@
+use bytes::Bytes;
+use jupyter::{JupyterErrorKind, JupyterMessage, SignatureScheme};
+
+mod common;
+
+const KEY: &str = "7c2d0e4a-9f1b-4c3d-8e5f-6a7b8c9d0e1f";
+
+/// The unsigned frames of each file in `tests/corpus`
+fn corpus() -> Vec<Vec<Bytes>> {
+    common::load_frames("corpus").into_iter().map(|(_, frames)| frames).collect()
+}
+
+/// Sign the unsigned seed with [`KEY`]
+fn signed(seed: &[Bytes]) -> Vec<Bytes> {
+    signed_by(seed, SignatureScheme::HmacSha256)
+}
+
+fn signed_by(seed: &[Bytes], scheme: SignatureScheme) -> Vec<Bytes> {
+    decode(seed.to_vec(), "").unwrap().encode(scheme, KEY).unwrap()
+}
+
+fn decode(frames: Vec<Bytes>, key: &str) -> Result<JupyterMessage, jupyter::JupyterError> {
+    JupyterMessage::decode(frames, SignatureScheme::HmacSha256, key)
+}
+
+fn expect_error(frames: Vec<Bytes>, key: &str, check: fn(&JupyterErrorKind) -> bool) {
+    match decode(frames, key) {
+        Ok(o) => panic!("Expected error, got {:?}", o),
+        Err(e) => assert!(check(e.kind()), "Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn decode_signed_corpus() {
+    for seed in corpus() {
+        let frames = signed(&seed);
+        let message = decode(frames.clone(), KEY).unwrap();
+        // encoding is stable once the message has been normalized
+        assert_eq!(message.encode(SignatureScheme::HmacSha256, KEY).unwrap(), frames);
+    }
+}
+
+#[test]
+fn decode_all_signature_schemes() {
+    let schemes = ["hmac-sha256", "hmac-sha384", "hmac-sha512", "hmac-md5"];
+    for seed in corpus() {
+        for name in schemes {
+            let scheme: SignatureScheme = name.parse().unwrap();
+            assert_eq!(scheme.as_str(), name);
+            let frames = signed_by(&seed, scheme);
+            assert!(JupyterMessage::decode(frames.clone(), scheme, KEY).is_ok());
+            for other in schemes.iter().filter(|v| **v != name) {
+                assert!(JupyterMessage::decode(frames.clone(), other.parse().unwrap(), KEY).is_err());
+            }
+        }
+    }
+    let error = "hmac-sha1".parse::<SignatureScheme>().unwrap_err();
+    assert!(matches!(error.kind(), JupyterErrorKind::UnknownSignatureScheme(s) if s == "hmac-sha1"));
+}
+
+#[test]
+fn reject_forged_message() {
+    for seed in corpus() {
+        let mut frames = signed(&seed);
+        let content = frames.len() - 1;
+        frames[content] = Bytes::from(r#"{"code":"std::process::exit(0)"}"#);
+        expect_error(frames.clone(), KEY, |e| matches!(e, JupyterErrorKind::BadSignature));
+        // a wrong key is as bad as a wrong signature
+        expect_error(signed(&seed), "another key", |e| matches!(e, JupyterErrorKind::BadSignature));
+        // so is a signature which is not hex
+        let mut frames = signed(&seed);
+        frames[2] = Bytes::from("not hex");
+        expect_error(frames, KEY, |e| matches!(e, JupyterErrorKind::BadSignature));
+    }
+}
+
+#[test]
+fn decode_buffers() {
+    for seed in corpus() {
+        let buffers = [Bytes::from_static(&[0, 159, 146, 150]), Bytes::from_static(b"arrow")];
+        let message = decode(seed.clone(), "").unwrap().with_buffers(buffers.clone());
+        let frames = message.encode(SignatureScheme::HmacSha256, KEY).unwrap();
+        assert_eq!(frames.len(), seed.len() + 2);
+        assert_eq!(decode(frames.clone(), KEY).unwrap().buffers(), buffers);
+        // buffers are covered by the signature
+        let mut forged = frames.clone();
+        forged[seed.len()] = Bytes::from_static(b"forged");
+        expect_error(forged, KEY, |e| matches!(e, JupyterErrorKind::BadSignature));
+        let mut dropped = frames;
+        dropped.pop();
+        expect_error(dropped, KEY, |e| matches!(e, JupyterErrorKind::BadSignature));
+    }
+}
+
+#[test]
+fn reject_malformed_message() {
+    let seed = corpus().remove(0);
+    let no_delimiter: Vec<Bytes> = seed.iter().filter(|v| &v[..] != b"<IDS|MSG>").cloned().collect();
+    expect_error(no_delimiter, "", |e| matches!(e, JupyterErrorKind::MissingDelimiter));
+    expect_error(seed[..2].to_vec(), "", |e| matches!(e, JupyterErrorKind::MalformedPart("signature", _)));
+    expect_error(seed[..5].to_vec(), "", |e| matches!(e, JupyterErrorKind::MissingParts(2)));
+    let mut broken = seed.clone();
+    broken[3] = Bytes::from("{\"msg_type\":");
+    expect_error(broken, "", |e| matches!(e, JupyterErrorKind::MalformedPart("header", _)));
+    let mut broken = seed;
+    broken[6] = Bytes::from(vec![0xff, 0xfe]);
+    expect_error(broken, "", |e| matches!(e, JupyterErrorKind::MalformedPart("content", _)));
+}
+
+/// Mutate the corpus randomly, the parser must never panic
+#[test]
+fn fuzz_corpus() {
+    let mut random = XorShift(0x2545_f491_4f6c_dd1d);
+    for seed in corpus() {
+        for (frames, key) in [(seed.clone(), ""), (signed(&seed), KEY)] {
+            for _ in 0..2000 {
+                let mut frames = frames.clone();
+                for _ in 0..random.below(4) + 1 {
+                    mutate(&mut frames, &mut random);
+                }
+                let _ = decode(frames, key);
+            }
+        }
+    }
+}
+
+fn mutate(frames: &mut Vec<Bytes>, random: &mut XorShift) {
+    if frames.is_empty() {
+        frames.push(Bytes::from("<IDS|MSG>"));
+        return;
+    }
+    let index = random.below(frames.len());
+    let mut frame = frames[index].to_vec();
+    match random.below(7) {
+        0 if !frame.is_empty() => {
+            let at = random.below(frame.len());
+            frame[at] ^= 1 << random.below(8);
+        }
+        1 => frame.truncate(random.below(frame.len() + 1)),
+        2 => frame.insert(random.below(frame.len() + 1), random.next_u64() as u8),
+        3 => {
+            frames.remove(index);
+            return;
+        }
+        4 => {
+            let copy = frames[index].clone();
+            frames.insert(index, copy);
+            return;
+        }
+        5 => {
+            let other = random.below(frames.len());
+            frames.swap(index, other);
+            return;
+        }
+        _ => {
+            // interesting tokens for the json parser
+            let tokens: [&[u8]; 6] = [b"{", b"}", b"\"", b"\\u", b"null", b"<IDS|MSG>"];
+            let token = tokens[random.below(tokens.len())];
+            let at = random.below(frame.len() + 1);
+            frame.splice(at..at, token.iter().copied());
+        }
+    }
+    frames[index] = Bytes::from(frame);
+}
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
@@ -0,0 +1,88 @@
+use bytes::Bytes;
+use jupyter::{JupyterMessage, SignatureScheme};
+use serde_json::{Map, Value};
+
+mod common;
+
+const KEY: &str = "b2f1c6e8-4d7a-4b3e-9c5f-0a1d2e3f4b5c";
+
+/// Each file in `tests/golden` is a json array of the header, parent header, metadata and content parts.
+///
+/// The files are written by hand after the messages of JupyterLab, they are not recorded traffic:
+/// ids have the `<uuid>_<pid>_<n>` form of `msg_id`, and `cell_id` and `subshell_id` are unknown header fields.
+/// Replace them with frames recorded from a JupyterLab session, and note its version here.
+fn golden() -> Vec<(String, Vec<Bytes>)> {
+    common::load_frames("golden")
+}
+
+fn header(frame: &Bytes) -> Map<String, Value> {
+    match serde_json::from_slice(frame).unwrap() {
+        Value::Object(o) => o,
+        v => panic!("Expected header object, got {}", v),
+    }
+}
+
+#[test]
+fn golden_header_round_trip() {
+    for (name, frames) in golden() {
+        let message = JupyterMessage::decode(frames.clone(), SignatureScheme::HmacSha256, "").unwrap();
+        let encoded = message.encode(SignatureScheme::HmacSha256, KEY).unwrap();
+        assert_eq!(encoded.len(), frames.len());
+        // the headers are sent back byte for byte
+        assert_eq!(encoded[3], frames[3], "{}", name);
+        assert_eq!(encoded[4], frames[4], "{}", name);
+        // metadata such as `cellId` is kept
+        assert_eq!(header(&encoded[5]), header(&frames[5]), "{}", name);
+        assert_eq!(message.metadata(), &Value::Object(header(&frames[5])), "{}", name);
+    }
+}
+
+#[test]
+fn golden_header_fields() {
+    for (name, frames) in golden() {
+        let message = JupyterMessage::decode(frames.clone(), SignatureScheme::HmacSha256, "").unwrap();
+        let request = header(&frames[3]);
+        assert_eq!(message.header().msg_id, request["msg_id"].as_str().unwrap(), "{}", name);
+        assert_eq!(message.header().session, request["session"].as_str().unwrap(), "{}", name);
+        for (key, value) in &message.header().extra {
+            assert_eq!(&request[key], value, "{}", name);
+        }
+        // a request without parent has an empty parent header
+        assert!(message.parent_header().msg_id.is_empty(), "{}", name);
+    }
+}
+
+#[test]
+fn golden_reply_parent_header() {
+    for (name, frames) in golden() {
+        let message = JupyterMessage::decode(frames.clone(), SignatureScheme::HmacSha256, "").unwrap();
+        let reply = message.as_reply().encode(SignatureScheme::HmacSha256, KEY).unwrap();
+        let request = header(&frames[3]);
+        // the frontend matches replies by the exact header of the request
+        assert_eq!(reply[4], frames[3], "{}", name);
+        assert_eq!(reply[5], "{}", "{}", name);
+        let reply = header(&reply[3]);
+        assert_eq!(reply["session"], request["session"], "{}", name);
+        assert_ne!(reply["msg_id"], request["msg_id"], "{}", name);
+        // replies carry the protocol version of the kernel, not the one of the request
+        assert_eq!(reply["version"], "5.5", "{}", name);
+        // replies go to the subshell of the request
+        assert_eq!(reply.get("subshell_id"), request.get("subshell_id"), "{}", name);
+        assert_eq!(reply.get("cell_id"), None, "{}", name);
+    }
+}
+
+#[test]
+fn reply_parent_header_verbatim() {
+    // no date, username or version, and an unusual date format
+    for request in [
+        r#"{"msg_id":"a1","msg_type":"kernel_info_request","session":"s1"}"#,
+        r#"{"date":"2024-03-01T10:00:00.123456Z","msg_id":"a2","msg_type":"kernel_info_request","session":"s1","username":"","version":"5.3"}"#,
+    ] {
+        let frames: Vec<Bytes> =
+            vec!["client-identity", "<IDS|MSG>", "", request, "{}", "{}", "{}"].into_iter().map(Bytes::from).collect();
+        let message = JupyterMessage::decode(frames, SignatureScheme::HmacSha256, "").unwrap();
+        let reply = message.as_reply().encode(SignatureScheme::HmacSha256, KEY).unwrap();
+        assert_eq!(reply[4], request);
+    }
+}